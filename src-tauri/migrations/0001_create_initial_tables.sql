@@ -0,0 +1,30 @@
+CREATE TABLE if not exists tags (
+  id TEXT NOT NULL UNIQUE,
+  color TEXT NOT NULL,
+  name TEXT NOT NULL,
+  PRIMARY KEY(id)
+);
+
+CREATE TABLE if not exists todoItems (
+  id TEXT NOT NULL UNIQUE,
+  title TEXT NOT NULL,
+  description TEXT,
+  notes TEXT,
+  isCompleted TEXT NOT NULL CHECK(isCompleted in ('true', 'false')),
+  dateCompleted TEXT,
+  timeCompleted TEXT,
+  timezoneCompleted TEXT,
+  dateCreated TEXT NOT NULL,
+  timeCreated TEXT NOT NULL,
+  timezoneCreated TEXT NOT NULL,
+  PRIMARY KEY(id)
+);
+
+CREATE TABLE if not exists todoItemsTags (
+  id TEXT NOT NULL UNIQUE,
+  todoItemId TEXT NOT NULL,
+  tagId TEXT NOT NULL,
+  PRIMARY KEY(id),
+  FOREIGN KEY(todoItemId) REFERENCES todoItems(id),
+  FOREIGN KEY(tagId) REFERENCES tags(id)
+);
@@ -0,0 +1,30 @@
+-- Links to todo items or tags that were deleted before foreign keys were
+-- enforced are orphans, and duplicate links keep only their first row.
+delete from todoItemsTags
+where
+  todoItemId not in (select id from todoItems)
+  or tagId not in (select id from tags);
+
+delete from todoItemsTags
+where rowid not in (
+  select min(rowid)
+  from todoItemsTags
+  group by todoItemId, tagId
+);
+
+CREATE TABLE todoItemsTags_new (
+  id TEXT NOT NULL UNIQUE,
+  todoItemId TEXT NOT NULL,
+  tagId TEXT NOT NULL,
+  PRIMARY KEY(id),
+  UNIQUE(todoItemId, tagId),
+  FOREIGN KEY(todoItemId) REFERENCES todoItems(id) ON DELETE CASCADE,
+  FOREIGN KEY(tagId) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+insert into todoItemsTags_new (id, todoItemId, tagId)
+select id, todoItemId, tagId from todoItemsTags;
+
+DROP TABLE todoItemsTags;
+
+ALTER TABLE todoItemsTags_new RENAME TO todoItemsTags;
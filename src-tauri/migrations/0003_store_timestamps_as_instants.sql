@@ -0,0 +1,69 @@
+-- Creation and completion used to be stored as separate date, time and UTC
+-- offset strings. They become UTC instants in RFC 3339 form, which sort and
+-- compare correctly as text. An offset doesn't identify a time zone, so the
+-- closest equivalent is the fixed-offset Etc/GMT zone (whose sign is inverted
+-- by convention), or UTC when there is none.
+CREATE TABLE todoItems_new (
+  id TEXT NOT NULL UNIQUE,
+  title TEXT NOT NULL,
+  description TEXT,
+  notes TEXT,
+  isCompleted TEXT NOT NULL CHECK(isCompleted in ('true', 'false')),
+  createdAt TEXT NOT NULL,
+  createdTimezone TEXT NOT NULL,
+  completedAt TEXT,
+  completedTimezone TEXT,
+  PRIMARY KEY(id)
+);
+
+insert into todoItems_new (
+  id,
+  title,
+  description,
+  notes,
+  isCompleted,
+  createdAt,
+  createdTimezone,
+  completedAt,
+  completedTimezone
+)
+select
+  id,
+  title,
+  description,
+  notes,
+  isCompleted,
+  coalesce(
+    strftime('%Y-%m-%dT%H:%M:%fZ', dateCreated || 'T' || timeCreated || timezoneCreated),
+    strftime('%Y-%m-%dT%H:%M:%fZ', dateCreated),
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+  ),
+  case
+    when timezoneCreated glob '[+-][0-9][0-9]:00' and substr(timezoneCreated, 2, 2) != '00' then
+      'Etc/GMT'
+      || case substr(timezoneCreated, 1, 1) when '+' then '-' else '+' end
+      || cast(substr(timezoneCreated, 2, 2) as integer)
+    else 'UTC'
+  end,
+  case
+    when dateCompleted is not null then
+      coalesce(
+        strftime('%Y-%m-%dT%H:%M:%fZ', dateCompleted || 'T' || timeCompleted || timezoneCompleted),
+        strftime('%Y-%m-%dT%H:%M:%fZ', dateCompleted)
+      )
+  end,
+  case
+    when dateCompleted is null then null
+    when timezoneCompleted glob '[+-][0-9][0-9]:00' and substr(timezoneCompleted, 2, 2) != '00' then
+      'Etc/GMT'
+      || case substr(timezoneCompleted, 1, 1) when '+' then '-' else '+' end
+      || cast(substr(timezoneCompleted, 2, 2) as integer)
+    else 'UTC'
+  end
+from todoItems;
+
+DROP TABLE todoItems;
+
+ALTER TABLE todoItems_new RENAME TO todoItems;
+
+CREATE INDEX todoItemsCompletedAt ON todoItems(completedAt);
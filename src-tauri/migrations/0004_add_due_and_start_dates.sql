@@ -0,0 +1,7 @@
+-- Due and start dates are calendar dates in the user's time zone rather than
+-- instants, so that "due Friday" stays on Friday wherever the app is opened.
+ALTER TABLE todoItems ADD COLUMN dueDate TEXT;
+ALTER TABLE todoItems ADD COLUMN dueTime TEXT;
+ALTER TABLE todoItems ADD COLUMN startDate TEXT;
+
+CREATE INDEX todoItemsDueDate ON todoItems(dueDate);
@@ -0,0 +1,10 @@
+-- A recurring item carries an RRULE. Completing it creates the next
+-- occurrence as a new item in the same series, so every occurrence keeps its
+-- own completion history. seriesId is the id of the series' first item and
+-- occurrence counts up from 1, which is what COUNT is checked against.
+ALTER TABLE todoItems ADD COLUMN recurrence TEXT;
+ALTER TABLE todoItems ADD COLUMN recurrenceMode TEXT CHECK (recurrenceMode IN ('due', 'completion'));
+ALTER TABLE todoItems ADD COLUMN seriesId TEXT;
+ALTER TABLE todoItems ADD COLUMN occurrence INTEGER;
+
+CREATE INDEX todoItemsSeriesId ON todoItems(seriesId, occurrence);
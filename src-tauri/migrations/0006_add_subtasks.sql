@@ -0,0 +1,6 @@
+-- Subtasks point at the item they belong to, nested to any depth. Deleting an
+-- item deletes everything nested under it.
+ALTER TABLE todoItems ADD COLUMN parentId TEXT REFERENCES todoItems(id) ON DELETE CASCADE;
+ALTER TABLE todoItems ADD COLUMN completesWithChildren TEXT NOT NULL DEFAULT 'false';
+
+CREATE INDEX todoItemsParentId ON todoItems(parentId);
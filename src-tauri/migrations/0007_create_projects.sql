@@ -0,0 +1,15 @@
+-- Projects group todo items; an item belongs to at most one. Deleting a
+-- project keeps its items and moves them out of it.
+CREATE TABLE projects (
+  id TEXT NOT NULL UNIQUE,
+  name TEXT NOT NULL,
+  color TEXT NOT NULL,
+  icon TEXT,
+  isArchived TEXT NOT NULL DEFAULT 'false' CHECK(isArchived in ('true', 'false')),
+  sortOrder INTEGER NOT NULL,
+  PRIMARY KEY(id)
+);
+
+ALTER TABLE todoItems ADD COLUMN projectId TEXT REFERENCES projects(id) ON DELETE SET NULL;
+
+CREATE INDEX todoItemsProjectId ON todoItems(projectId);
@@ -0,0 +1,24 @@
+-- Items are listed in the order of their rank (see src/rank.rs), so moving an
+-- item only updates its own row. Existing items keep the order they were
+-- created in, as four-character ranks with room for over 200,000 items.
+ALTER TABLE todoItems ADD COLUMN position TEXT;
+
+WITH
+  numbered AS (
+    SELECT id, row_number() OVER (ORDER BY createdAt, id) - 1 AS n
+    FROM todoItems
+  ),
+  digits(value) AS (
+    SELECT '0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz'
+  )
+UPDATE todoItems
+SET position = (
+  SELECT 'c'
+    || substr(digits.value, n / 3844 % 62 + 1, 1)
+    || substr(digits.value, n / 62 % 62 + 1, 1)
+    || substr(digits.value, n % 62 + 1, 1)
+  FROM numbered, digits
+  WHERE numbered.id = todoItems.id
+);
+
+CREATE INDEX todoItemsPosition ON todoItems(position);
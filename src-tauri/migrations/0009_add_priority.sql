@@ -0,0 +1,4 @@
+-- Priority runs from 0 (none) to 4 (urgent) so that it sorts numerically.
+ALTER TABLE todoItems ADD COLUMN priority INTEGER NOT NULL DEFAULT 0 CHECK(priority BETWEEN 0 AND 4);
+
+CREATE INDEX todoItemsPriority ON todoItems(priority);
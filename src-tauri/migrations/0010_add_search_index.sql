@@ -0,0 +1,29 @@
+-- A full-text index over the text of every todo item, kept in sync by
+-- triggers. It's a standalone table keyed by the item id rather than an
+-- external content table, because external content is keyed by rowid and
+-- rebuilding todoItems would change its rowids.
+CREATE VIRTUAL TABLE todoItemsSearch USING fts5(
+  id UNINDEXED,
+  title,
+  description,
+  notes,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO todoItemsSearch (id, title, description, notes)
+SELECT id, title, description, notes FROM todoItems;
+
+CREATE TRIGGER todoItemsSearchInsert AFTER INSERT ON todoItems BEGIN
+  INSERT INTO todoItemsSearch (id, title, description, notes)
+  VALUES (new.id, new.title, new.description, new.notes);
+END;
+
+CREATE TRIGGER todoItemsSearchUpdate AFTER UPDATE OF title, description, notes ON todoItems BEGIN
+  UPDATE todoItemsSearch
+  SET title = new.title, description = new.description, notes = new.notes
+  WHERE id = old.id;
+END;
+
+CREATE TRIGGER todoItemsSearchDelete AFTER DELETE ON todoItems BEGIN
+  DELETE FROM todoItemsSearch WHERE id = old.id;
+END;
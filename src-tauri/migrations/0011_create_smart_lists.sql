@@ -0,0 +1,9 @@
+-- Smart lists are saved filters, listed alongside projects. Their items are
+-- found by compiling the filter each time the list is opened.
+CREATE TABLE smartLists (
+  id TEXT NOT NULL UNIQUE,
+  name TEXT NOT NULL,
+  filter TEXT NOT NULL,
+  sortOrder INTEGER NOT NULL,
+  PRIMARY KEY(id)
+);
@@ -0,0 +1,16 @@
+-- Deleting a todo item or tag moves it to the trash by setting deletedAt.
+-- Trashed rows are left out of everything but the trash, and are deleted for
+-- good once they've been there longer than the trash retention setting.
+ALTER TABLE todoItems ADD COLUMN deletedAt TEXT;
+ALTER TABLE tags ADD COLUMN deletedAt TEXT;
+
+CREATE INDEX todoItemsDeletedAt ON todoItems(deletedAt);
+CREATE INDEX tagsDeletedAt ON tags(deletedAt);
+
+CREATE TABLE settings (
+  key TEXT NOT NULL UNIQUE,
+  value TEXT NOT NULL,
+  PRIMARY KEY(key)
+);
+
+INSERT INTO settings (key, value) VALUES ('trashRetentionDays', '30');
@@ -0,0 +1,17 @@
+-- The undo history. Each step is one command, and its changes are the SQL
+-- statements that flip the step: undoing runs them in reverse and replaces
+-- them with what's needed to redo it, and the other way around.
+CREATE TABLE journalSteps (
+  id INTEGER PRIMARY KEY,
+  description TEXT NOT NULL,
+  createdAt TEXT NOT NULL,
+  isUndone TEXT NOT NULL DEFAULT 'false' CHECK(isUndone in ('true', 'false'))
+);
+
+CREATE TABLE journalChanges (
+  id INTEGER PRIMARY KEY,
+  stepId INTEGER NOT NULL REFERENCES journalSteps(id) ON DELETE CASCADE,
+  sql TEXT NOT NULL
+);
+
+CREATE INDEX journalChangesStepId ON journalChanges(stepId);
@@ -0,0 +1,83 @@
+-- An append-only history of each todo item. Triggers record every change to
+-- the item's fields, its completion, trashing and tags, however it was made.
+CREATE TABLE todoItemEvents (
+  id INTEGER PRIMARY KEY,
+  todoItemId TEXT NOT NULL REFERENCES todoItems(id) ON DELETE CASCADE,
+  occurredAt TEXT NOT NULL,
+  kind TEXT NOT NULL CHECK(kind in (
+    'created',
+    'fieldChanged',
+    'completed',
+    'uncompleted',
+    'deleted',
+    'restored',
+    'tagAdded',
+    'tagRemoved'
+  )),
+  field TEXT,
+  oldValue TEXT,
+  newValue TEXT
+);
+
+CREATE INDEX todoItemEventsTodoItemId ON todoItemEvents(todoItemId);
+
+-- Items that already exist start their history when they were created and,
+-- if they're done, when they were completed. Items completed before
+-- completion times were kept have none, so they count from their creation.
+INSERT INTO todoItemEvents (todoItemId, occurredAt, kind)
+SELECT id, createdAt, 'created' FROM todoItems;
+
+INSERT INTO todoItemEvents (todoItemId, occurredAt, kind)
+SELECT id, coalesce(completedAt, createdAt), 'completed'
+FROM todoItems
+WHERE isCompleted = 'true';
+
+CREATE TRIGGER todoItemEventsInsert AFTER INSERT ON todoItems BEGIN
+  INSERT INTO todoItemEvents (todoItemId, occurredAt, kind)
+  VALUES (new.id, new.createdAt, 'created');
+END;
+
+CREATE TRIGGER todoItemEventsUpdate AFTER UPDATE ON todoItems BEGIN
+  INSERT INTO todoItemEvents (todoItemId, occurredAt, kind, field, oldValue, newValue)
+  SELECT new.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'fieldChanged', field, oldValue, newValue
+  FROM (
+    SELECT 'title' AS field, old.title AS oldValue, new.title AS newValue
+    UNION ALL SELECT 'description', old.description, new.description
+    UNION ALL SELECT 'notes', old.notes, new.notes
+    UNION ALL SELECT 'dueDate', old.dueDate, new.dueDate
+    UNION ALL SELECT 'dueTime', old.dueTime, new.dueTime
+    UNION ALL SELECT 'startDate', old.startDate, new.startDate
+    UNION ALL SELECT 'priority', old.priority, new.priority
+    UNION ALL SELECT 'recurrence', old.recurrence, new.recurrence
+    UNION ALL SELECT 'recurrenceMode', old.recurrenceMode, new.recurrenceMode
+    UNION ALL SELECT 'parentId', old.parentId, new.parentId
+    UNION ALL SELECT 'projectId', old.projectId, new.projectId
+    UNION ALL SELECT 'completesWithChildren', old.completesWithChildren, new.completesWithChildren
+  )
+  WHERE oldValue IS NOT newValue;
+
+  INSERT INTO todoItemEvents (todoItemId, occurredAt, kind)
+  SELECT
+    new.id,
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+    CASE new.isCompleted WHEN 'true' THEN 'completed' ELSE 'uncompleted' END
+  WHERE old.isCompleted IS NOT new.isCompleted;
+
+  INSERT INTO todoItemEvents (todoItemId, occurredAt, kind)
+  SELECT
+    new.id,
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+    CASE WHEN new.deletedAt IS NULL THEN 'restored' ELSE 'deleted' END
+  WHERE old.deletedAt IS NOT new.deletedAt;
+END;
+
+CREATE TRIGGER todoItemEventsTagInsert AFTER INSERT ON todoItemsTags BEGIN
+  INSERT INTO todoItemEvents (todoItemId, occurredAt, kind, newValue)
+  VALUES (new.todoItemId, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'tagAdded', new.tagId);
+END;
+
+CREATE TRIGGER todoItemEventsTagDelete AFTER DELETE ON todoItemsTags BEGIN
+  INSERT INTO todoItemEvents (todoItemId, occurredAt, kind, oldValue)
+  SELECT old.todoItemId, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'tagRemoved', old.tagId
+  WHERE EXISTS (SELECT 1 FROM todoItems WHERE id = old.todoItemId);
+END;
@@ -0,0 +1,3 @@
+-- The calendar feed is a file other apps can subscribe to, so it's only
+-- written once the user turns it on.
+INSERT INTO settings (key, value) VALUES ('icalFeedEnabled', 'false');
@@ -1,2 +1,7 @@
+pub mod interchange;
+pub mod journal;
+pub mod projects;
+pub mod smart_lists;
 pub mod tags;
 pub mod todo_items;
+pub mod trash;
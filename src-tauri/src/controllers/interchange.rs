@@ -0,0 +1,171 @@
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::interchange::csv;
+use crate::interchange::ical;
+use crate::interchange::json;
+use crate::interchange::markdown;
+use crate::interchange::taskwarrior;
+use crate::interchange::todo_txt;
+use crate::interchange::ImportSummary;
+use crate::timestamp;
+use chrono::Utc;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+#[specta::specta]
+pub fn export_data(database: State<Database>, path: String) -> Result<(), AppError> {
+    return json::export(&mut database.lock(), &path, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn import_data(
+    database: State<Database>,
+    path: String,
+    mode: json::ImportMode,
+) -> Result<ImportSummary, AppError> {
+    return json::import(&mut database.lock(), &path, mode);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn export_todo_txt(database: State<Database>, path: String) -> Result<(), AppError> {
+    return todo_txt::export(&mut database.lock(), &path, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn import_todo_txt(
+    database: State<Database>,
+    path: String,
+    timezone: String,
+) -> Result<ImportSummary, AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return todo_txt::import(&mut database.lock(), &path, timezone, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn export_ical(database: State<Database>, path: String) -> Result<(), AppError> {
+    return ical::export(&mut database.lock(), &path, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn import_ical(
+    database: State<Database>,
+    path: String,
+    timezone: String,
+) -> Result<ImportSummary, AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return ical::import(&mut database.lock(), &path, timezone, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_ical_feed(
+    database: State<Database>,
+    app_handle: AppHandle,
+) -> Result<ical::FeedStatus, AppError> {
+    let directory = database::data_dir(&app_handle)?;
+
+    return ical::get_feed_status(&mut database.lock(), &directory);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_ical_feed_enabled(database: State<Database>, enabled: bool) -> Result<(), AppError> {
+    return ical::set_feed_enabled(&mut database.lock(), enabled);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn preview_csv(path: String, limit: u32) -> Result<csv::CsvPreview, AppError> {
+    return csv::preview(&path, limit);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn import_csv(
+    database: State<Database>,
+    path: String,
+    mapping: csv::CsvMapping,
+    timezone: String,
+) -> Result<csv::CsvImportReport, AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return csv::import(&mut database.lock(), &path, &mapping, timezone, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn export_csv(
+    database: State<Database>,
+    path: String,
+    fields: Vec<csv::Field>,
+) -> Result<(), AppError> {
+    return csv::export(&mut database.lock(), &path, &fields, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn render_markdown(
+    database: State<Database>,
+    options: markdown::MarkdownOptions,
+) -> Result<String, AppError> {
+    return markdown::render(&mut database.lock(), &options, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn export_markdown(
+    database: State<Database>,
+    path: String,
+    options: markdown::MarkdownOptions,
+) -> Result<(), AppError> {
+    return markdown::export(&mut database.lock(), &path, &options, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_default_markdown_template() -> String {
+    return String::from(markdown::DEFAULT_TEMPLATE);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn import_markdown(
+    database: State<Database>,
+    path: String,
+    timezone: String,
+) -> Result<ImportSummary, AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return markdown::import(&mut database.lock(), &path, timezone, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn export_taskwarrior(
+    database: State<Database>,
+    path: String,
+    timezone: String,
+) -> Result<(), AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return taskwarrior::export(&mut database.lock(), &path, timezone, Utc::now());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn import_taskwarrior(
+    database: State<Database>,
+    path: String,
+    timezone: String,
+) -> Result<ImportSummary, AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return taskwarrior::import(&mut database.lock(), &path, timezone, Utc::now());
+}
@@ -0,0 +1,22 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::journal;
+use tauri::State;
+
+#[tauri::command]
+#[specta::specta]
+pub fn undo(database: State<Database>) -> Result<Option<journal::JournalStep>, AppError> {
+    return journal::undo(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn redo(database: State<Database>) -> Result<Option<journal::JournalStep>, AppError> {
+    return journal::redo(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_journal_state(database: State<Database>) -> Result<journal::JournalState, AppError> {
+    return journal::get_state(&mut database.lock());
+}
@@ -0,0 +1,54 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::models::project;
+use tauri::State;
+use uuid::Uuid;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_projects(database: State<Database>) -> Result<Vec<project::Project>, AppError> {
+    return project::get_all(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_project(
+    database: State<Database>,
+    name: String,
+    color: String,
+    icon: Option<String>,
+) -> Result<project::Project, AppError> {
+    let id = Uuid::new_v4().to_string();
+    return project::create(&mut database.lock(), id, name, color, icon);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_project(
+    database: State<Database>,
+    id: String,
+    name: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
+    sort_order: Option<i64>,
+) -> Result<project::Project, AppError> {
+    let mut connection = database.lock();
+    project::update(&mut connection, id.clone(), name, color, icon, sort_order)?;
+    return project::get(&mut connection, id);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_project_archived(
+    database: State<Database>,
+    id: String,
+    is_archived: bool,
+) -> Result<(), AppError> {
+    return project::set_archived(&mut database.lock(), id, is_archived);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_project(database: State<Database>, id: String) -> Result<(), AppError> {
+    return project::delete(&mut database.lock(), id);
+}
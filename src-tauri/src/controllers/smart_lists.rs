@@ -0,0 +1,80 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::filter;
+use crate::models::smart_list;
+use crate::models::todo_item;
+use crate::timestamp;
+use tauri::State;
+use uuid::Uuid;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_smart_lists(database: State<Database>) -> Result<Vec<smart_list::SmartList>, AppError> {
+    return smart_list::get_all(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_smart_list(
+    database: State<Database>,
+    name: String,
+    filter: String,
+) -> Result<smart_list::SmartList, AppError> {
+    let id = Uuid::new_v4().to_string();
+    return smart_list::create(&mut database.lock(), id, name, filter);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_smart_list(
+    database: State<Database>,
+    id: String,
+    name: Option<String>,
+    filter: Option<String>,
+    sort_order: Option<i64>,
+) -> Result<smart_list::SmartList, AppError> {
+    let mut connection = database.lock();
+    smart_list::update(&mut connection, id.clone(), name, filter, sort_order)?;
+    return smart_list::get(&mut connection, id);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_smart_list(database: State<Database>, id: String) -> Result<(), AppError> {
+    return smart_list::delete(&mut database.lock(), id);
+}
+
+/// The items matching the smart list's filter, with relative dates in it
+/// counted from `date`.
+#[tauri::command]
+#[specta::specta]
+pub fn evaluate_smart_list(
+    database: State<Database>,
+    id: String,
+    date: String,
+    sort: todo_item::SortOrder,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+    let mut connection = database.lock();
+
+    let smart_list = smart_list::get(&mut connection, id)?;
+    let filter = filter::compile(&smart_list.filter, date)?;
+
+    return todo_item::get_filtered(&mut connection, &filter, sort);
+}
+
+/// Like `evaluate_smart_list`, for a filter that hasn't been saved, so the UI
+/// can preview a filter while it's being typed.
+#[tauri::command]
+#[specta::specta]
+pub fn evaluate_filter(
+    database: State<Database>,
+    filter: String,
+    date: String,
+    sort: todo_item::SortOrder,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+    let filter = filter::compile(&filter, date)?;
+
+    return todo_item::get_filtered(&mut database.lock(), &filter, sort);
+}
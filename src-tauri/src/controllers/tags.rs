@@ -1,29 +1,56 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::journal;
 use crate::models::tag;
-use tauri::AppHandle;
+use chrono::Utc;
+use tauri::State;
 use uuid::Uuid;
 
 #[tauri::command]
-pub fn get_tags(app_handle: AppHandle) -> String {
-    return serde_json::to_string(&tag::get_all(&app_handle).unwrap()).unwrap();
+#[specta::specta]
+pub fn get_tags(database: State<Database>) -> Result<Vec<tag::Tag>, AppError> {
+    return tag::get_all(&mut database.lock());
 }
 
 #[tauri::command]
-pub fn create_tag(app_handle: AppHandle, name: String, color: String) -> String {
+#[specta::specta]
+pub fn create_tag(
+    database: State<Database>,
+    name: String,
+    color: String,
+) -> Result<tag::Tag, AppError> {
     let id = Uuid::new_v4().to_string();
-    let tag = tag::create(&app_handle, id, name, color);
-    return serde_json::to_string(&tag.unwrap()).unwrap();
+    return journal::record(&mut database.lock(), "Create tag", |connection| {
+        tag::create(connection, id, name, color)
+    });
 }
 
 #[tauri::command]
-pub fn update_tag(app_handle: AppHandle, id: String, name: Option<String>, color: Option<String>) {
-    if tag::update(&app_handle, id, name, color).is_err() {
-        println!("Failed to update tag");
-    }
+#[specta::specta]
+pub fn update_tag(
+    database: State<Database>,
+    id: String,
+    name: Option<String>,
+    color: Option<String>,
+) -> Result<tag::Tag, AppError> {
+    return journal::record(&mut database.lock(), "Edit tag", |connection| {
+        tag::update(connection, id.clone(), name, color)?;
+        tag::get(connection, id)
+    });
 }
 
 #[tauri::command]
-pub fn delete_tag(app_handle: AppHandle, id: String) {
-    if tag::delete(&app_handle, id).is_err() {
-        println!("Failed to delete tag");
-    }
+#[specta::specta]
+pub fn delete_tag(database: State<Database>, id: String) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Delete tag", |connection| {
+        tag::delete(connection, id, Utc::now())
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn restore_tag(database: State<Database>, id: String) -> Result<tag::Tag, AppError> {
+    return journal::record(&mut database.lock(), "Restore tag", |connection| {
+        tag::restore(connection, id)
+    });
 }
@@ -1,99 +1,462 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::journal;
+use crate::models::tag;
 use crate::models::todo_item;
-use tauri::AppHandle;
+use crate::models::todo_item_event;
+use crate::recurrence::{RecurrenceMode, Rule};
+use crate::timestamp;
+use chrono::Utc;
+use std::collections::HashMap;
+use tauri::State;
 use uuid::Uuid;
 
 #[tauri::command]
-pub fn get_todo_items(app_handle: AppHandle, date_completed: String) -> String {
-    let todo_items = todo_item::get_all(&app_handle, date_completed).unwrap();
+#[specta::specta]
+pub fn get_todo_items(
+    database: State<Database>,
+    date: String,
+    timezone: String,
+    sort: todo_item::SortOrder,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+    let timezone = timestamp::parse_timezone(&timezone)?;
 
-    return serde_json::to_string(&todo_items).unwrap();
+    return todo_item::get_all(&mut database.lock(), date, timezone, None, sort);
 }
 
 #[tauri::command]
+#[specta::specta]
+pub fn get_project_todo_items(
+    database: State<Database>,
+    project_id: String,
+    date: String,
+    timezone: String,
+    sort: todo_item::SortOrder,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return todo_item::get_all(&mut database.lock(), date, timezone, Some(project_id), sort);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn search_todo_items(
+    database: State<Database>,
+    query: String,
+    filters: todo_item::SearchFilters,
+) -> Result<Vec<todo_item::SearchResult>, AppError> {
+    return todo_item::search(&mut database.lock(), &query, filters);
+}
+
+#[tauri::command]
+#[specta::specta]
 pub fn create_todo_item(
-    app_handle: AppHandle,
+    database: State<Database>,
     title: String,
-    date_created: String,
-    time_created: String,
-    timezone_created: String,
-) -> String {
+    timezone: String,
+    parent_id: Option<String>,
+    project_id: Option<String>,
+) -> Result<todo_item::TodoItem, AppError> {
     let id = Uuid::new_v4().to_string();
-    let todo_item = todo_item::create(
-        &app_handle,
-        id,
-        title,
-        date_created,
-        time_created,
-        timezone_created,
-    )
-    .unwrap();
-    return serde_json::to_string(&todo_item).unwrap();
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(&mut database.lock(), "Add todo item", |connection| {
+        todo_item::create(
+            connection,
+            id,
+            title,
+            Utc::now(),
+            timezone,
+            parent_id,
+            project_id,
+        )
+    });
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn complete_todo_item(
-    app_handle: AppHandle,
+    database: State<Database>,
+    id: String,
+    completed_at: String,
+    timezone: String,
+) -> Result<Option<todo_item::TodoItem>, AppError> {
+    let completed_at = timestamp::parse_instant(&completed_at)?;
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(&mut database.lock(), "Complete todo item", |connection| {
+        todo_item::complete(connection, id, completed_at, timezone)
+    });
+}
+
+/// Completes every item in `ids` as one step, so they're undone together.
+/// Returns the next occurrences of the recurring ones.
+#[tauri::command]
+#[specta::specta]
+pub fn complete_todo_items(
+    database: State<Database>,
+    ids: Vec<String>,
+    completed_at: String,
+    timezone: String,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let completed_at = timestamp::parse_instant(&completed_at)?;
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(&mut database.lock(), "Complete todo items", |connection| {
+        let mut next_occurrences = Vec::new();
+
+        for id in ids {
+            next_occurrences.extend(todo_item::complete(connection, id, completed_at, timezone)?);
+        }
+
+        Ok(next_occurrences)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn uncomplete_todo_item(database: State<Database>, id: String) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Uncomplete todo item", |connection| {
+        todo_item::uncomplete(connection, id)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_todo_item_due_date(
+    database: State<Database>,
     id: String,
-    date_completed: String,
-    time_completed: String,
-    timezone_completed: String,
-) {
-    if todo_item::complete(
-        &app_handle,
-        id,
-        date_completed,
-        time_completed,
-        timezone_completed,
-    )
-    .is_err()
-    {
-        println!("Failed to complete todo item");
-    }
+    due_date: Option<String>,
+    due_time: Option<String>,
+) -> Result<(), AppError> {
+    let due_date = due_date
+        .map(|due_date| timestamp::parse_date(&due_date))
+        .transpose()?;
+    let due_time = due_time
+        .map(|due_time| timestamp::parse_time(&due_time))
+        .transpose()?;
+
+    return journal::record(&mut database.lock(), "Change due date", |connection| {
+        todo_item::set_due_date(connection, id, due_date, due_time)
+    });
 }
 
 #[tauri::command]
-pub fn uncomplete_todo_item(app_handle: AppHandle, id: String) {
-    if todo_item::uncomplete(&app_handle, id).is_err() {
-        println!("Failed to uncomplete todo item");
-    }
+#[specta::specta]
+pub fn set_todo_item_priority(
+    database: State<Database>,
+    id: String,
+    priority: todo_item::Priority,
+) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Change priority", |connection| {
+        todo_item::set_priority(connection, id, priority)
+    });
 }
 
 #[tauri::command]
+#[specta::specta]
+pub fn set_todo_item_start_date(
+    database: State<Database>,
+    id: String,
+    start_date: Option<String>,
+) -> Result<(), AppError> {
+    let start_date = start_date
+        .map(|start_date| timestamp::parse_date(&start_date))
+        .transpose()?;
+
+    return journal::record(&mut database.lock(), "Change start date", |connection| {
+        todo_item::set_start_date(connection, id, start_date)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn move_todo_item(
+    database: State<Database>,
+    id: String,
+    before_id: Option<String>,
+    after_id: Option<String>,
+) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Reorder todo item", |connection| {
+        todo_item::move_item(connection, id, before_id, after_id)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_todo_item_children(
+    database: State<Database>,
+    id: String,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    return todo_item::get_children(&mut database.lock(), id);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_todo_item_parent(
+    database: State<Database>,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Move todo item", |connection| {
+        todo_item::set_parent(connection, id, parent_id)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_todo_item_project(
+    database: State<Database>,
+    id: String,
+    project_id: Option<String>,
+) -> Result<(), AppError> {
+    return journal::record(
+        &mut database.lock(),
+        "Move todo item to project",
+        |connection| todo_item::set_project(connection, id, project_id),
+    );
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_todo_item_completes_with_children(
+    database: State<Database>,
+    id: String,
+    completes_with_children: bool,
+    timezone: String,
+) -> Result<(), AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(
+        &mut database.lock(),
+        "Change whether todo item completes with subtasks",
+        |connection| {
+            todo_item::set_completes_with_children(
+                connection,
+                id,
+                completes_with_children,
+                Utc::now(),
+                timezone,
+            )
+        },
+    );
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_todo_item_recurrence(
+    database: State<Database>,
+    id: String,
+    rule: String,
+    mode: RecurrenceMode,
+) -> Result<(), AppError> {
+    let rule = Rule::parse(&rule)?;
+
+    return journal::record(&mut database.lock(), "Change recurrence", |connection| {
+        todo_item::set_recurrence(connection, id, &rule, mode)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn skip_todo_item_occurrence(
+    database: State<Database>,
+    id: String,
+    date: String,
+    timezone: String,
+) -> Result<todo_item::TodoItem, AppError> {
+    let date = timestamp::parse_date(&date)?;
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(&mut database.lock(), "Skip occurrence", |connection| {
+        todo_item::skip_occurrence(connection, id, date, Utc::now(), timezone)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn end_todo_item_recurrence(database: State<Database>, id: String) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "End recurrence", |connection| {
+        todo_item::end_recurrence(connection, id)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_todo_items_due_on(
+    database: State<Database>,
+    date: String,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+
+    return todo_item::get_due_on(&mut database.lock(), date);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_overdue_todo_items(
+    database: State<Database>,
+    date: String,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+
+    return todo_item::get_overdue(&mut database.lock(), date);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_upcoming_todo_items(
+    database: State<Database>,
+    date: String,
+    days: u32,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+
+    return todo_item::get_upcoming(&mut database.lock(), date, days);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_undated_todo_items(
+    database: State<Database>,
+    date: String,
+) -> Result<Vec<todo_item::TodoItem>, AppError> {
+    let date = timestamp::parse_date(&date)?;
+
+    return todo_item::get_undated(&mut database.lock(), date);
+}
+
+#[tauri::command]
+#[specta::specta]
 pub fn update_todo_item(
-    app_handle: AppHandle,
+    database: State<Database>,
     id: String,
     title: Option<String>,
     description: Option<String>,
     notes: Option<String>,
-) {
-    if todo_item::update(&app_handle, id, title, description, notes).is_err() {
-        println!("Failed to update todo item");
-    }
+) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Edit todo item", |connection| {
+        todo_item::update(connection, id, title, description, notes)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_todo_item(
+    database: State<Database>,
+    id: String,
+    timezone: String,
+) -> Result<(), AppError> {
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(&mut database.lock(), "Delete todo item", |connection| {
+        todo_item::delete(connection, id, Utc::now(), timezone)
+    });
+}
+
+/// Moves every item in `ids` to the trash as one step, so they're undone
+/// together.
+#[tauri::command]
+#[specta::specta]
+pub fn delete_todo_items(
+    database: State<Database>,
+    ids: Vec<String>,
+    timezone: String,
+) -> Result<(), AppError> {
+    let deleted_at = Utc::now();
+    let timezone = timestamp::parse_timezone(&timezone)?;
+
+    return journal::record(&mut database.lock(), "Delete todo items", |connection| {
+        for id in ids {
+            todo_item::delete(connection, id, deleted_at, timezone)?;
+        }
+
+        Ok(())
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn restore_todo_item(
+    database: State<Database>,
+    id: String,
+) -> Result<todo_item::TodoItem, AppError> {
+    return journal::record(&mut database.lock(), "Restore todo item", |connection| {
+        todo_item::restore(connection, id)
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_todo_item_history(
+    database: State<Database>,
+    id: String,
+) -> Result<Vec<todo_item_event::TodoItemEvent>, AppError> {
+    return todo_item_event::get_history(&mut database.lock(), id);
+}
+
+/// Puts the title, description or notes back to what they were before the
+/// event, and returns the item.
+#[tauri::command]
+#[specta::specta]
+pub fn restore_todo_item_field(
+    database: State<Database>,
+    id: String,
+    event_id: i64,
+) -> Result<todo_item::TodoItem, AppError> {
+    return journal::record(
+        &mut database.lock(),
+        "Restore earlier version",
+        |connection| {
+            todo_item_event::restore_field(connection, id.clone(), event_id)?;
+            todo_item::get(connection, &id)
+        },
+    );
 }
 
 #[tauri::command]
-pub fn delete_todo_item(app_handle: AppHandle, id: String) {
-    if todo_item::delete(&app_handle, id).is_err() {
-        println!("Failed to delete todo item");
-    }
+#[specta::specta]
+pub fn add_tag_to_todo_item(
+    database: State<Database>,
+    todo_item_id: String,
+    tag_id: String,
+) -> Result<(), AppError> {
+    return journal::record(&mut database.lock(), "Add tag to todo item", |connection| {
+        todo_item::add_tag(connection, todo_item_id, tag_id)
+    });
 }
 
 #[tauri::command]
-pub fn add_tag_to_todo_item(app_handle: AppHandle, todo_item_id: String, tag_id: String) {
-    if todo_item::add_tag(&app_handle, todo_item_id, tag_id).is_err() {
-        println!("Failed to add tag to todo item");
-    }
+#[specta::specta]
+pub fn remove_tag_from_todo_item(
+    database: State<Database>,
+    todo_item_id: String,
+    tag_id: String,
+) -> Result<(), AppError> {
+    return journal::record(
+        &mut database.lock(),
+        "Remove tag from todo item",
+        |connection| todo_item::remove_tag(connection, todo_item_id, tag_id),
+    );
 }
 
 #[tauri::command]
-pub fn remove_tag_from_todo_item(app_handle: AppHandle, todo_item_id: String, tag_id: String) {
-    if todo_item::remove_tag(&app_handle, todo_item_id, tag_id).is_err() {
-        println!("Failed to remove tag from todo item");
-    }
+#[specta::specta]
+pub fn get_todo_item_tags(
+    database: State<Database>,
+    todo_item_id: String,
+) -> Result<Vec<tag::Tag>, AppError> {
+    return todo_item::get_tags(&mut database.lock(), todo_item_id);
 }
 
+/// The tags on each of the items, for items loaded without them.
 #[tauri::command]
-pub fn get_todo_item_tags(app_handle: AppHandle, todo_item_id: String) -> String {
-    return serde_json::to_string(&todo_item::get_tags(&app_handle, todo_item_id).unwrap())
-        .unwrap();
+#[specta::specta]
+pub fn get_tags_for_items(
+    database: State<Database>,
+    ids: Vec<String>,
+) -> Result<HashMap<String, Vec<tag::Tag>>, AppError> {
+    return todo_item::get_tags_for_items(&mut database.lock(), ids);
 }
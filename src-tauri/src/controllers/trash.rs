@@ -0,0 +1,28 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::models::trash;
+use tauri::State;
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_trash(database: State<Database>) -> Result<trash::Trash, AppError> {
+    return trash::get(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn empty_trash(database: State<Database>) -> Result<(), AppError> {
+    return trash::empty(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_trash_retention_days(database: State<Database>) -> Result<u32, AppError> {
+    return trash::get_retention_days(&mut database.lock());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_trash_retention_days(database: State<Database>, days: u32) -> Result<(), AppError> {
+    return trash::set_retention_days(&mut database.lock(), days);
+}
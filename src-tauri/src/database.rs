@@ -1,78 +1,223 @@
-use sqlite::Connection;
+use crate::error::AppError;
+use crate::journal;
 use sqlite::Error;
+use sqlite::Statement;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::time::Duration;
+use tauri::AppHandle;
 
-fn create_tags_table(connection: &Connection) -> bool {
-    let statement = String::from(
-        "
-    CREATE TABLE if not exists tags (
-      id TEXT NOT NULL UNIQUE,
-      color TEXT NOT NULL,
-      name TEXT NOT NULL,
-      PRIMARY KEY(id)
-    );
-  ",
-    );
-
-    return connection.execute(statement).is_ok();
-}
+pub mod migrations;
 
-fn create_todo_items_table(connection: &Connection) -> bool {
-    let statement = String::from(
-        "
-      CREATE TABLE if not exists todoItems (
-        id TEXT NOT NULL UNIQUE,
-        title TEXT NOT NULL,
-        description TEXT,
-        notes TEXT,
-        isCompleted TEXT NOT NULL CHECK(isCompleted in ('true', 'false')),
-        dateCompleted TEXT,
-        timeCompleted TEXT,
-        timezoneCompleted TEXT,
-        dateCreated TEXT NOT NULL,
-        timeCreated TEXT NOT NULL,
-        timezoneCreated TEXT NOT NULL,
-        PRIMARY KEY(id)
-      );
-    ",
-    );
-
-    return connection.execute(statement).is_ok();
+pub struct Connection {
+    connection: &'static sqlite::Connection,
+    statements: HashMap<String, Statement<'static>>,
+    write_signal: Arc<WriteSignal>,
 }
 
-fn create_todo_items_tags_table(connection: &Connection) -> bool {
-    let statement = String::from(
-        "
-      CREATE TABLE if not exists todoItemsTags (
-        id TEXT NOT NULL UNIQUE,
-        todoItemId TEXT NOT NULL,
-        tagId TEXT NOT NULL,
-        PRIMARY KEY(id),
-        FOREIGN KEY(todoItemId) REFERENCES todoItems(id),
-        FOREIGN KEY(tagId) REFERENCES tags(id)
-      );
-    ",
-    );
-
-    return connection.execute(statement).is_ok();
+// SAFETY: the connection and its cached statements are only reachable through
+// the mutex in `Database`, so they are never used from two threads at once.
+unsafe impl Send for Connection {}
+
+impl Connection {
+    /// Returns a reset statement for `sql`, preparing it on first use and
+    /// reusing the cached statement afterwards.
+    pub fn prepare(&mut self, sql: &str) -> Result<&mut Statement<'static>, Error> {
+        let statement = match self.statements.entry(String::from(sql)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(self.connection.prepare(sql)?),
+        };
+
+        statement.reset()?;
+
+        Ok(statement)
+    }
+
+    /// Prepares a statement without caching it, for SQL built from what the
+    /// user typed, which would otherwise fill the cache with statements that
+    /// never run again.
+    pub fn prepare_uncached(&self, sql: &str) -> Result<Statement<'static>, Error> {
+        return self.connection.prepare(sql);
+    }
+
+    pub fn execute(&self, sql: &str) -> Result<(), Error> {
+        return self.connection.execute(sql);
+    }
+
+    /// The number of rows modified by the most recent insert, update or delete.
+    pub fn changes(&mut self) -> Result<i64, Error> {
+        let statement = self.prepare("select changes()")?;
+
+        statement.next()?;
+
+        return statement.read::<i64>(0);
+    }
+
+    /// The number of rows modified since the connection was opened, which
+    /// goes up whenever anything is written.
+    pub fn total_changes(&mut self) -> Result<i64, Error> {
+        let statement = self.prepare("select total_changes()")?;
+
+        statement.next()?;
+
+        return statement.read::<i64>(0);
+    }
+
+    /// Returns `NotFound` when the most recent statement didn't modify any rows.
+    pub fn expect_changes(&mut self, message: &str) -> Result<(), AppError> {
+        if self.changes()? == 0 {
+            return Err(AppError::NotFound(String::from(message)));
+        }
+
+        Ok(())
+    }
+
+    /// Wakes whatever waits on the database's `WriteSignal`, once something
+    /// that others follow has been written.
+    pub fn notify_written(&self) {
+        self.write_signal.notify();
+    }
+
+    /// Runs `action` in a savepoint, so that whatever it changed is rolled
+    /// back if it fails.
+    pub fn savepoint<T>(
+        &mut self,
+        action: impl FnOnce(&mut Connection) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        self.execute("savepoint action")?;
+
+        // Releasing can fail too, when it commits and a deferred foreign key
+        // is still violated, and then the savepoint is still open.
+        match action(self).and_then(|value| {
+            self.execute("release action")?;
+            Ok(value)
+        }) {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                self.execute("rollback to action")?;
+                self.execute("release action")?;
+                Err(error)
+            }
+        }
+    }
 }
 
-pub fn create_tables(connection: &Connection) -> bool {
-    return create_tags_table(connection)
-        && create_todo_items_table(connection)
-        && create_todo_items_tags_table(connection);
+/// Lets a thread sleep until something is written to the database, for work
+/// that follows the data, such as keeping the calendar feed up to date.
+#[derive(Default)]
+pub struct WriteSignal {
+    is_written: Mutex<bool>,
+    condvar: Condvar,
 }
 
-pub fn initialize_database(app_handle: &tauri::AppHandle) -> Result<Connection, Error> {
-    let path = app_handle.path_resolver().app_data_dir().unwrap();
-    let path_string = path.display();
+impl WriteSignal {
+    fn notify(&self) {
+        *self
+            .is_written
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = true;
+
+        self.condvar.notify_all();
+    }
+
+    /// Waits until something is written, or until `timeout` has passed if
+    /// there is one. Writes made while nothing was waiting count too, so none
+    /// are missed between waits.
+    pub fn wait(&self, timeout: Option<Duration>) {
+        let mut is_written = self
+            .is_written
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        while !*is_written {
+            match timeout {
+                Some(timeout) => {
+                    let (guard, result) = self
+                        .condvar
+                        .wait_timeout(is_written, timeout)
+                        .unwrap_or_else(PoisonError::into_inner);
+
+                    is_written = guard;
 
-    if !path.exists() {
-        std::fs::create_dir_all(&path).unwrap();
+                    if result.timed_out() {
+                        break;
+                    }
+                }
+                None => {
+                    is_written = self
+                        .condvar
+                        .wait(is_written)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+
+        *is_written = false;
     }
+}
+
+/// The directory the database and the files the app writes beside it live in.
+pub fn data_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    return app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| AppError::Io(String::from("Could not resolve the app data directory")));
+}
+
+/// The app's database connection, registered as managed state at startup.
+pub struct Database(Mutex<Connection>, Arc<WriteSignal>);
+
+impl Database {
+    pub fn open(app_handle: &AppHandle) -> Result<Database, AppError> {
+        let path = data_dir(app_handle)?;
+        let path_string = path.display();
 
-    let connection = sqlite::open(format!("{}{}", path_string, String::from("/database.db")))?;
+        if !path.exists() {
+            std::fs::create_dir_all(&path)?;
+        }
 
-    create_tables(&connection);
+        let mut connection =
+            sqlite::open(format!("{}{}", path_string, String::from("/database.db")))?;
 
-    return Ok(connection);
+        connection.set_busy_timeout(5000)?;
+
+        migrations::run(&connection)?;
+
+        // SQLite doesn't enforce foreign keys unless asked to, per connection.
+        connection.execute("pragma foreign_keys = on")?;
+
+        // The connection lives for as long as the app does, and leaking it
+        // lets cached statements borrow it without a self-referential struct.
+        let connection: &'static sqlite::Connection = Box::leak(Box::new(connection));
+
+        let write_signal = Arc::new(WriteSignal::default());
+
+        let mut connection = Connection {
+            connection,
+            statements: HashMap::new(),
+            write_signal: write_signal.clone(),
+        };
+
+        journal::install(&mut connection)?;
+
+        return Ok(Database(Mutex::new(connection), write_signal));
+    }
+
+    /// Notified after commands change items or tags, undo or redo, and
+    /// imports.
+    pub fn write_signal(&self) -> Arc<WriteSignal> {
+        return self.1.clone();
+    }
+
+    pub fn lock(&self) -> MutexGuard<Connection> {
+        // A panic while holding the lock can't leave the connection itself in
+        // a bad state, so keep serving commands instead of failing all of them.
+        return self.0.lock().unwrap_or_else(PoisonError::into_inner);
+    }
 }
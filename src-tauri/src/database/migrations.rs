@@ -0,0 +1,160 @@
+use crate::error::AppError;
+use sqlite::Connection;
+use sqlite::Error;
+use sqlite::State;
+
+struct Migration {
+    name: &'static str,
+    sql: &'static str,
+}
+
+// Migrations are applied in order and must never be edited or reordered once
+// released. The schema version of a database is the number of migrations that
+// have been applied to it, and is stored in `PRAGMA user_version`.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        name: "create_initial_tables",
+        sql: include_str!("../../migrations/0001_create_initial_tables.sql"),
+    },
+    Migration {
+        name: "cascade_todo_items_tags",
+        sql: include_str!("../../migrations/0002_cascade_todo_items_tags.sql"),
+    },
+    Migration {
+        name: "store_timestamps_as_instants",
+        sql: include_str!("../../migrations/0003_store_timestamps_as_instants.sql"),
+    },
+    Migration {
+        name: "add_due_and_start_dates",
+        sql: include_str!("../../migrations/0004_add_due_and_start_dates.sql"),
+    },
+    Migration {
+        name: "add_recurrence",
+        sql: include_str!("../../migrations/0005_add_recurrence.sql"),
+    },
+    Migration {
+        name: "add_subtasks",
+        sql: include_str!("../../migrations/0006_add_subtasks.sql"),
+    },
+    Migration {
+        name: "create_projects",
+        sql: include_str!("../../migrations/0007_create_projects.sql"),
+    },
+    Migration {
+        name: "add_positions",
+        sql: include_str!("../../migrations/0008_add_positions.sql"),
+    },
+    Migration {
+        name: "add_priority",
+        sql: include_str!("../../migrations/0009_add_priority.sql"),
+    },
+    Migration {
+        name: "add_search_index",
+        sql: include_str!("../../migrations/0010_add_search_index.sql"),
+    },
+    Migration {
+        name: "create_smart_lists",
+        sql: include_str!("../../migrations/0011_create_smart_lists.sql"),
+    },
+    Migration {
+        name: "add_trash",
+        sql: include_str!("../../migrations/0012_add_trash.sql"),
+    },
+    Migration {
+        name: "create_journal",
+        sql: include_str!("../../migrations/0013_create_journal.sql"),
+    },
+    Migration {
+        name: "create_todo_item_events",
+        sql: include_str!("../../migrations/0014_create_todo_item_events.sql"),
+    },
+    Migration {
+        name: "add_ical_feed_setting",
+        sql: include_str!("../../migrations/0015_add_ical_feed_setting.sql"),
+    },
+];
+
+pub fn latest_version() -> i64 {
+    return MIGRATIONS.len() as i64;
+}
+
+fn get_user_version(connection: &Connection) -> Result<i64, Error> {
+    let mut statement = connection.prepare("pragma user_version")?;
+
+    if let State::Row = statement.next()? {
+        return statement.read::<i64>(0);
+    }
+
+    Ok(0)
+}
+
+fn check_foreign_keys(connection: &Connection) -> Result<(), AppError> {
+    let mut statement = connection.prepare("pragma foreign_key_check")?;
+
+    if let State::Row = statement.next()? {
+        return Err(AppError::Database(format!(
+            "Rows in {} violate a foreign key constraint",
+            statement.read::<String>(0)?
+        )));
+    }
+
+    Ok(())
+}
+
+fn apply_in_transaction(
+    connection: &Connection,
+    version: i64,
+    migration: &Migration,
+) -> Result<(), AppError> {
+    connection.execute(migration.sql)?;
+    check_foreign_keys(connection)?;
+    connection.execute(format!("pragma user_version = {}", version))?;
+
+    Ok(())
+}
+
+fn apply(connection: &Connection, version: i64, migration: &Migration) -> Result<(), AppError> {
+    connection.execute("begin")?;
+
+    if let Err(error) = apply_in_transaction(connection, version, migration) {
+        connection.execute("rollback")?;
+        return Err(match error {
+            AppError::Database(message) => AppError::Database(format!(
+                "Migration {} ({}) failed: {}",
+                version, migration.name, message
+            )),
+            error => error,
+        });
+    }
+
+    connection.execute("commit")?;
+
+    Ok(())
+}
+
+pub fn run(connection: &Connection) -> Result<(), AppError> {
+    let current_version = get_user_version(connection)?;
+
+    if current_version > latest_version() {
+        return Err(AppError::Database(format!(
+            "Database schema version {} is newer than the latest version {} supported by this app",
+            current_version,
+            latest_version()
+        )));
+    }
+
+    // Migrations that rebuild a table drop the old one, which would cascade to
+    // every row referencing it if foreign keys were enforced. Each migration
+    // checks the constraints itself before committing instead.
+    connection.execute("pragma foreign_keys = off")?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate() {
+        let version = index as i64 + 1;
+
+        if version > current_version {
+            apply(connection, version, migration)?;
+        }
+    }
+
+    Ok(())
+}
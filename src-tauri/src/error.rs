@@ -0,0 +1,90 @@
+use crate::filter::FilterError;
+use serde::Serialize;
+use std::fmt;
+
+/// The error type returned by every command. It serializes to
+/// `{ "kind": "notFound", "message": "..." }` so the frontend can branch on
+/// `kind` and show `message` to the user. Invalid filters carry a
+/// [`FilterError`] as their message, with where in the filter the error is.
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum AppError {
+    NotFound(String),
+    Validation(String),
+    Database(String),
+    Conflict(String),
+    Io(String),
+    InvalidFilter(FilterError),
+}
+
+// https://www.sqlite.org/rescode.html#constraint
+const SQLITE_CONSTRAINT: isize = 19;
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(message) => write!(f, "Not found: {}", message),
+            AppError::Validation(message) => write!(f, "Invalid input: {}", message),
+            AppError::Database(message) => write!(f, "Database error: {}", message),
+            AppError::Conflict(message) => write!(f, "Conflict: {}", message),
+            AppError::Io(message) => write!(f, "I/O error: {}", message),
+            AppError::InvalidFilter(error) => write!(
+                f,
+                "Invalid filter at {}-{}: {}",
+                error.start, error.end, error.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<sqlite::Error> for AppError {
+    fn from(error: sqlite::Error) -> Self {
+        let message = error
+            .message
+            .unwrap_or_else(|| String::from("Unknown database error"));
+
+        match error.code {
+            Some(SQLITE_CONSTRAINT) => AppError::Conflict(message),
+            _ => AppError::Database(message),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::Io(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        AppError::Validation(error.to_string())
+    }
+}
+
+impl From<csv::Error> for AppError {
+    fn from(error: csv::Error) -> Self {
+        match error.kind() {
+            csv::ErrorKind::Io(_) => AppError::Io(error.to_string()),
+            _ => AppError::Validation(error.to_string()),
+        }
+    }
+}
+
+// Tera's errors only say which step failed, and keep what went wrong in the
+// errors they wrap.
+impl From<tera::Error> for AppError {
+    fn from(error: tera::Error) -> Self {
+        let mut message = error.to_string();
+        let mut source = std::error::Error::source(&error);
+
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+
+        AppError::Validation(message)
+    }
+}
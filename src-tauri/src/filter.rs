@@ -0,0 +1,920 @@
+//! A small query language for filtering todo items, compiled to SQL over
+//! `todoItems`.
+//!
+//! A filter is a list of terms that must all match, such as
+//! `tag:work due<=+3d !completed priority>=high`. Terms can be combined with
+//! `and`, `or` and parentheses, and negated with `!`, `-` or `not`.
+//!
+//! - `tag:name` and `project:name` match by name, ignoring case. `tag:none`
+//!   and `project:none` match items without any.
+//! - `due` and `start` compare dates with `:`, `=`, `!=`, `<`, `<=`, `>` or
+//!   `>=`. A date is `YYYY-MM-DD`, `today`, `tomorrow`, `yesterday`, a number
+//!   of days, weeks or months from today like `+3d`, `-1w` or `+2m`, or `none`.
+//! - `priority` compares with the same operators against `none`, `low`,
+//!   `medium`, `high` or `urgent`.
+//! - `completed`, `recurring`, `subtask` and `overdue` match items that are.
+//! - Any other word or `"quoted phrase"` is searched for in the title,
+//!   description and notes.
+
+use crate::error::AppError;
+use crate::models::todo_item::Priority;
+use crate::search;
+use crate::timestamp;
+use chrono::{Duration, Months, NaiveDate};
+use serde::Serialize;
+
+/// Where a filter is invalid, so the UI can underline it. `start` and `end`
+/// are UTF-16 offsets into the filter, the way JavaScript indexes strings.
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterError {
+    pub message: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A filter as an SQL condition on `todoItems`, with the values to bind to
+/// its named parameters.
+#[derive(Debug)]
+pub struct CompiledFilter {
+    pub sql: String,
+    pub parameters: Vec<(String, String)>,
+    /// Whether the filter picks items by project, in which case items in
+    /// archived projects aren't hidden from it.
+    pub mentions_project: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    fn to_sql(self) -> &'static str {
+        match self {
+            Operator::Equal => "=",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    OpenParen,
+    CloseParen,
+    Not,
+    And,
+    Or,
+    Word(String),
+    Phrase(String),
+    Field {
+        name: String,
+        name_span: Span,
+        operator: Operator,
+        operator_span: Span,
+        value: String,
+        value_span: Span,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+// Byte offsets are used while parsing and only turned into UTF-16 offsets
+// for the error.
+fn error(filter: &str, span: Span, message: String) -> AppError {
+    let offset = |index: usize| filter[..index].encode_utf16().count() as u32;
+
+    AppError::InvalidFilter(FilterError {
+        message,
+        start: offset(span.start),
+        end: offset(span.end),
+    })
+}
+
+struct Lexer<'a> {
+    filter: &'a str,
+    index: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.filter[self.index..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += c.len_utf8();
+        Some(c)
+    }
+
+    fn phrase(&mut self) -> Result<(String, Span), AppError> {
+        let start = self.index;
+        self.bump();
+
+        let content_start = self.index;
+
+        while let Some(c) = self.bump() {
+            if c == '"' {
+                let span = Span {
+                    start,
+                    end: self.index,
+                };
+
+                return Ok((
+                    String::from(&self.filter[content_start..self.index - 1]),
+                    span,
+                ));
+            }
+        }
+
+        Err(error(
+            self.filter,
+            Span {
+                start,
+                end: self.index,
+            },
+            String::from("This quote is never closed"),
+        ))
+    }
+
+    fn word(&mut self) -> (String, Span) {
+        let start = self.index;
+
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                break;
+            }
+
+            self.bump();
+        }
+
+        (
+            String::from(&self.filter[start..self.index]),
+            Span {
+                start,
+                end: self.index,
+            },
+        )
+    }
+
+    fn tokens(mut self) -> Result<Vec<Token>, AppError> {
+        let mut tokens = Vec::new();
+
+        while let Some(c) = self.peek() {
+            let start = self.index;
+
+            let kind = if c.is_whitespace() {
+                self.bump();
+                continue;
+            } else if c == '(' {
+                self.bump();
+                TokenKind::OpenParen
+            } else if c == ')' {
+                self.bump();
+                TokenKind::CloseParen
+            } else if c == '!' || c == '-' {
+                self.bump();
+
+                match self.peek() {
+                    Some(next) if !next.is_whitespace() => TokenKind::Not,
+                    _ => {
+                        return Err(error(
+                            self.filter,
+                            Span {
+                                start,
+                                end: self.index,
+                            },
+                            format!("Put what to exclude right after {}", c),
+                        ))
+                    }
+                }
+            } else if c == '"' {
+                let (phrase, span) = self.phrase()?;
+
+                tokens.push(Token {
+                    kind: TokenKind::Phrase(phrase),
+                    span,
+                });
+                continue;
+            } else {
+                let (word, span) = self.word();
+                self.field_or_word(word, span)?
+            };
+
+            tokens.push(Token {
+                kind,
+                span: Span {
+                    start,
+                    end: self.index,
+                },
+            });
+        }
+
+        Ok(tokens)
+    }
+
+    fn field_or_word(&mut self, word: String, span: Span) -> Result<TokenKind, AppError> {
+        let operators = [
+            ("<=", Operator::LessOrEqual),
+            (">=", Operator::GreaterOrEqual),
+            ("!=", Operator::NotEqual),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+            ("=", Operator::Equal),
+            (":", Operator::Equal),
+        ];
+
+        let found = word.char_indices().find_map(|(index, _)| {
+            operators
+                .iter()
+                .find(|(text, _)| word[index..].starts_with(text))
+                .map(|(text, operator)| (index, text.len(), *operator))
+        });
+
+        let (index, length, operator) = match found {
+            Some(found) => found,
+            None => {
+                return Ok(match word.to_lowercase().as_str() {
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    _ => TokenKind::Word(word),
+                })
+            }
+        };
+
+        let name_span = Span {
+            start: span.start,
+            end: span.start + index,
+        };
+        let operator_span = Span {
+            start: name_span.end,
+            end: name_span.end + length,
+        };
+
+        if index == 0 {
+            return Err(error(
+                self.filter,
+                operator_span,
+                format!(
+                    "Put a field like due or priority before {}",
+                    &word[..length]
+                ),
+            ));
+        }
+
+        let (value, value_span) = if operator_span.end < span.end {
+            (
+                String::from(&word[index + length..]),
+                Span {
+                    start: operator_span.end,
+                    end: span.end,
+                },
+            )
+        } else if self.peek() == Some('"') {
+            self.phrase()?
+        } else {
+            return Err(error(
+                self.filter,
+                operator_span,
+                format!("Put a value after {}", &word[index..index + length]),
+            ));
+        };
+
+        Ok(TokenKind::Field {
+            name: word[..index].to_lowercase(),
+            name_span,
+            operator,
+            operator_span,
+            value,
+            value_span,
+        })
+    }
+}
+
+struct Compiler<'a> {
+    filter: &'a str,
+    tokens: Vec<Token>,
+    index: usize,
+    today: NaiveDate,
+    parameters: Vec<(String, String)>,
+    mentions_project: bool,
+}
+
+impl<'a> Compiler<'a> {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.index).map(|token| &token.kind)
+    }
+
+    fn end_span(&self) -> Span {
+        Span {
+            start: self.filter.len(),
+            end: self.filter.len(),
+        }
+    }
+
+    fn parameter(&mut self, value: String) -> String {
+        let name = format!(":p{}", self.parameters.len());
+        self.parameters.push((name.clone(), value));
+        name
+    }
+
+    fn or(&mut self) -> Result<String, AppError> {
+        let mut sql = self.and()?;
+
+        while let Some(TokenKind::Or) = self.peek() {
+            self.index += 1;
+            sql = format!("({} or {})", sql, self.and()?);
+        }
+
+        Ok(sql)
+    }
+
+    fn and(&mut self) -> Result<String, AppError> {
+        let mut sql = self.unary()?;
+
+        loop {
+            match self.peek() {
+                Some(TokenKind::And) => {
+                    self.index += 1;
+                }
+                Some(TokenKind::Or) | Some(TokenKind::CloseParen) | None => break,
+                // Terms next to each other must both match.
+                Some(_) => {}
+            }
+
+            sql = format!("({} and {})", sql, self.unary()?);
+        }
+
+        Ok(sql)
+    }
+
+    fn unary(&mut self) -> Result<String, AppError> {
+        if let Some(TokenKind::Not) = self.peek() {
+            self.index += 1;
+            return Ok(format!("not ({})", self.unary()?));
+        }
+
+        self.term()
+    }
+
+    fn term(&mut self) -> Result<String, AppError> {
+        let token = match self.tokens.get(self.index) {
+            Some(token) => token.clone(),
+            None => {
+                return Err(error(
+                    self.filter,
+                    self.end_span(),
+                    String::from("The filter ends where a term was expected"),
+                ))
+            }
+        };
+
+        self.index += 1;
+
+        match token.kind {
+            TokenKind::OpenParen => {
+                let sql = self.or()?;
+
+                match self.peek() {
+                    Some(TokenKind::CloseParen) => {
+                        self.index += 1;
+                        Ok(sql)
+                    }
+                    _ => Err(error(
+                        self.filter,
+                        token.span,
+                        String::from("This parenthesis is never closed"),
+                    )),
+                }
+            }
+            TokenKind::CloseParen => Err(error(
+                self.filter,
+                token.span,
+                String::from("This parenthesis was never opened"),
+            )),
+            TokenKind::And | TokenKind::Or => Err(error(
+                self.filter,
+                token.span,
+                format!(
+                    "Put a term before {}",
+                    &self.filter[token.span.start..token.span.end]
+                ),
+            )),
+            TokenKind::Not => unreachable!("not is handled by unary"),
+            TokenKind::Word(word) => self.word(word, token.span),
+            TokenKind::Phrase(phrase) => self.text(&format!("\"{}\"", phrase), token.span),
+            TokenKind::Field {
+                name,
+                name_span,
+                operator,
+                operator_span,
+                value,
+                value_span,
+            } => match name.as_str() {
+                "tag" => self.tag(operator, operator_span, value),
+                "project" => self.project(operator, operator_span, value),
+                "due" => self.date("dueDate", operator, value, value_span),
+                "start" => self.date("startDate", operator, value, value_span),
+                "priority" => self.priority(operator, value, value_span),
+                _ => Err(error(
+                    self.filter,
+                    name_span,
+                    format!(
+                        "There's no {} field. Try tag, project, due, start or priority",
+                        name
+                    ),
+                )),
+            },
+        }
+    }
+
+    fn word(&mut self, word: String, span: Span) -> Result<String, AppError> {
+        match word.to_lowercase().as_str() {
+            "completed" => Ok(String::from("isCompleted = 'true'")),
+            "recurring" => Ok(String::from("recurrence is not null")),
+            "subtask" => Ok(String::from("parentId is not null")),
+            "overdue" => {
+                let today = self.parameter(timestamp::date_to_sql(&self.today));
+
+                Ok(format!(
+                    "(isCompleted = 'false' and coalesce(dueDate < {}, 0))",
+                    today
+                ))
+            }
+            _ => self.text(&word, span),
+        }
+    }
+
+    fn text(&mut self, text: &str, span: Span) -> Result<String, AppError> {
+        let query = search::to_fts_query(text)?.ok_or_else(|| {
+            error(
+                self.filter,
+                span,
+                String::from("There's nothing to search for here"),
+            )
+        })?;
+        let query = self.parameter(query);
+
+        Ok(format!(
+            "id in (select id from todoItemsSearch where todoItemsSearch match {})",
+            query
+        ))
+    }
+
+    fn equality(&self, operator: Operator, operator_span: Span) -> Result<bool, AppError> {
+        match operator {
+            Operator::Equal => Ok(true),
+            Operator::NotEqual => Ok(false),
+            _ => Err(error(
+                self.filter,
+                operator_span,
+                String::from("Only :, = and != work here"),
+            )),
+        }
+    }
+
+    fn tag(&mut self, operator: Operator, span: Span, value: String) -> Result<String, AppError> {
+        let is_equal = self.equality(operator, span)?;
+
+        let sql = if value.eq_ignore_ascii_case("none") {
+            String::from(
+                "
+                id not in (
+                  select todoItemsTags.todoItemId
+                  from todoItemsTags
+                  join tags on tags.id = todoItemsTags.tagId
+                  where tags.deletedAt is null
+                )
+              ",
+            )
+        } else {
+            let name = self.parameter(value);
+
+            format!(
+                "
+                id in (
+                  select todoItemsTags.todoItemId
+                  from todoItemsTags
+                  join tags on tags.id = todoItemsTags.tagId
+                  where
+                    tags.name = {} collate nocase
+                    and tags.deletedAt is null
+                )
+              ",
+                name
+            )
+        };
+
+        Ok(if is_equal {
+            sql
+        } else {
+            format!("not ({})", sql)
+        })
+    }
+
+    fn project(
+        &mut self,
+        operator: Operator,
+        span: Span,
+        value: String,
+    ) -> Result<String, AppError> {
+        let is_equal = self.equality(operator, span)?;
+        self.mentions_project = true;
+
+        let sql = if value.eq_ignore_ascii_case("none") {
+            String::from("projectId is null")
+        } else {
+            let name = self.parameter(value);
+
+            format!(
+                "coalesce(projectId, '') in (select id from projects where name = {} collate nocase)",
+                name
+            )
+        };
+
+        Ok(if is_equal {
+            sql
+        } else {
+            format!("not ({})", sql)
+        })
+    }
+
+    fn date(
+        &mut self,
+        column: &str,
+        operator: Operator,
+        value: String,
+        span: Span,
+    ) -> Result<String, AppError> {
+        if value.eq_ignore_ascii_case("none") {
+            return match operator {
+                Operator::Equal => Ok(format!("{} is null", column)),
+                Operator::NotEqual => Ok(format!("{} is not null", column)),
+                _ => Err(error(
+                    self.filter,
+                    span,
+                    String::from("none can only be compared with :, = or !="),
+                )),
+            };
+        }
+
+        let date = self.parse_date(&value).ok_or_else(|| {
+            error(
+                self.filter,
+                span,
+                format!(
+                    "{} isn't a date. Try YYYY-MM-DD, today, tomorrow or +3d",
+                    value
+                ),
+            )
+        })?;
+        let date = self.parameter(timestamp::date_to_sql(&date));
+
+        // Items without the date don't match, and do match when negated.
+        Ok(format!(
+            "coalesce({} {} {}, 0)",
+            column,
+            operator.to_sql(),
+            date
+        ))
+    }
+
+    fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        match value.to_lowercase().as_str() {
+            "today" => return Some(self.today),
+            "tomorrow" => return self.today.succ_opt(),
+            "yesterday" => return self.today.pred_opt(),
+            _ => {}
+        }
+
+        if let Ok(date) = timestamp::parse_date(value) {
+            return Some(date);
+        }
+
+        let (sign, rest) = match value.chars().next()? {
+            '+' => (1, &value[1..]),
+            '-' => (-1, &value[1..]),
+            _ => (1, value),
+        };
+        let unit = rest.chars().last()?;
+        let amount = rest[..rest.len() - unit.len_utf8()].parse::<u32>().ok()?;
+
+        match (unit.to_ascii_lowercase(), sign) {
+            ('d', _) => self
+                .today
+                .checked_add_signed(Duration::days(sign * i64::from(amount))),
+            ('w', _) => self
+                .today
+                .checked_add_signed(Duration::weeks(sign * i64::from(amount))),
+            ('m', 1) => self.today.checked_add_months(Months::new(amount)),
+            ('m', _) => self.today.checked_sub_months(Months::new(amount)),
+            _ => None,
+        }
+    }
+
+    fn priority(
+        &mut self,
+        operator: Operator,
+        value: String,
+        span: Span,
+    ) -> Result<String, AppError> {
+        let priority = match value.to_lowercase().as_str() {
+            "none" => Priority::None,
+            "low" => Priority::Low,
+            "medium" => Priority::Medium,
+            "high" => Priority::High,
+            "urgent" => Priority::Urgent,
+            _ => {
+                return Err(error(
+                    self.filter,
+                    span,
+                    format!(
+                        "{} isn't a priority. Try none, low, medium, high or urgent",
+                        value
+                    ),
+                ))
+            }
+        };
+
+        Ok(format!(
+            "priority {} {}",
+            operator.to_sql(),
+            priority.to_sql()
+        ))
+    }
+}
+
+/// Compiles `filter` to SQL, resolving relative dates against `today`. An
+/// empty filter matches every item.
+pub fn compile(filter: &str, today: NaiveDate) -> Result<CompiledFilter, AppError> {
+    let tokens = Lexer { filter, index: 0 }.tokens()?;
+
+    let mut compiler = Compiler {
+        filter,
+        tokens,
+        index: 0,
+        today,
+        parameters: Vec::new(),
+        mentions_project: false,
+    };
+
+    if compiler.tokens.is_empty() {
+        return Ok(CompiledFilter {
+            sql: String::from("1"),
+            parameters: Vec::new(),
+            mentions_project: false,
+        });
+    }
+
+    let sql = compiler.or()?;
+
+    if let Some(token) = compiler.tokens.get(compiler.index) {
+        return Err(error(
+            filter,
+            token.span,
+            String::from("This parenthesis was never opened"),
+        ));
+    }
+
+    Ok(CompiledFilter {
+        sql,
+        parameters: compiler.parameters,
+        mentions_project: compiler.mentions_project,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+    }
+
+    fn compile_today(filter: &str) -> CompiledFilter {
+        compile(filter, today()).unwrap()
+    }
+
+    fn values(filter: &str) -> Vec<String> {
+        compile_today(filter)
+            .parameters
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    fn compile_error(filter: &str) -> (String, u32, u32) {
+        match compile(filter, today()) {
+            Err(AppError::InvalidFilter(error)) => (error.message, error.start, error.end),
+            result => panic!("{} compiled to {:?}", filter, result),
+        }
+    }
+
+    #[test]
+    fn matches_everything_when_empty() {
+        assert_eq!(compile_today("").sql, "1");
+        assert_eq!(compile_today("  ").sql, "1");
+    }
+
+    #[test]
+    fn combines_terms() {
+        assert_eq!(
+            compile_today("completed or recurring subtask").sql,
+            "(isCompleted = 'true' or (recurrence is not null and parentId is not null))"
+        );
+        assert_eq!(
+            compile_today("(completed OR recurring) and subtask").sql,
+            "((isCompleted = 'true' or recurrence is not null) and parentId is not null)"
+        );
+        assert_eq!(
+            compile_today("!completed").sql,
+            "not (isCompleted = 'true')"
+        );
+        assert_eq!(
+            compile_today("-completed").sql,
+            compile_today("not completed").sql
+        );
+        assert_eq!(values("overdue"), vec!["2026-03-01"]);
+    }
+
+    #[test]
+    fn compares_relative_dates() {
+        let filter = compile_today("due<=+3d");
+
+        assert_eq!(filter.sql, "coalesce(dueDate <= :p0, 0)");
+        assert_eq!(values("due<=+3d"), vec!["2026-03-04"]);
+        assert_eq!(values("due:3D"), vec!["2026-03-04"]);
+        assert_eq!(values("due>-2w"), vec!["2026-02-15"]);
+        assert_eq!(values("start:+2m"), vec!["2026-05-01"]);
+        assert_eq!(values("start:-1M"), vec!["2026-02-01"]);
+        assert_eq!(
+            values("due:today due:tomorrow due:yesterday due:2026-12-25"),
+            vec!["2026-03-01", "2026-03-02", "2026-02-28", "2026-12-25"]
+        );
+    }
+
+    #[test]
+    fn adds_months_up_to_the_end_of_the_month() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        let filter = compile("due<+1m due>-2m", today).unwrap();
+
+        assert_eq!(
+            filter
+                .parameters
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect::<Vec<_>>(),
+            vec!["2026-02-28", "2025-11-30"]
+        );
+    }
+
+    #[test]
+    fn compares_missing_dates() {
+        assert_eq!(compile_today("due:none").sql, "dueDate is null");
+        assert_eq!(compile_today("start!=NONE").sql, "startDate is not null");
+        assert_eq!(
+            compile_error("due<none"),
+            (
+                String::from("none can only be compared with :, = or !="),
+                4,
+                8
+            )
+        );
+        assert_eq!(
+            compile_error("due:+3x"),
+            (
+                String::from("+3x isn't a date. Try YYYY-MM-DD, today, tomorrow or +3d"),
+                4,
+                7
+            )
+        );
+        assert_eq!(compile_error("due:2026-02-30").1, 4);
+    }
+
+    #[test]
+    fn compares_priorities() {
+        assert_eq!(compile_today("priority>=high").sql, "priority >= 3");
+        assert_eq!(compile_today("priority:Urgent").sql, "priority = 4");
+        assert_eq!(compile_today("priority!=none").sql, "priority != 0");
+        assert_eq!(compile_today("priority<medium").sql, "priority < 2");
+        assert_eq!(
+            compile_error("priority>meh"),
+            (
+                String::from("meh isn't a priority. Try none, low, medium, high or urgent"),
+                9,
+                12
+            )
+        );
+    }
+
+    #[test]
+    fn matches_tags_and_projects() {
+        let filter = compile_today("tag:Work");
+
+        assert!(filter.sql.contains("tags.name = :p0 collate nocase"));
+        assert_eq!(values("tag:Work"), vec!["Work"]);
+        assert!(!filter.mentions_project);
+
+        assert!(compile_today("tag!=work").sql.starts_with("not ("));
+        assert!(compile_today("tag:none").sql.contains("id not in"));
+        assert!(compile_today("tag:none").parameters.is_empty());
+
+        let filter = compile_today("project:Home");
+
+        assert!(filter.mentions_project);
+        assert_eq!(values("project:Home"), vec!["Home"]);
+        assert_eq!(compile_today("project:none").sql, "projectId is null");
+
+        assert_eq!(
+            compile_error("tag<work"),
+            (String::from("Only :, = and != work here"), 3, 4)
+        );
+        assert_eq!(
+            compile_error("project>=home"),
+            (String::from("Only :, = and != work here"), 7, 9)
+        );
+    }
+
+    #[test]
+    fn reads_quoted_values() {
+        assert_eq!(values("tag:\"deep focus\""), vec!["deep focus"]);
+        assert_eq!(
+            values("project=\"Home Renovation\" completed"),
+            vec!["Home Renovation"]
+        );
+        assert_eq!(values("\"call the bank\""), vec!["(\"call the bank\")"]);
+        assert_eq!(values("bank"), vec!["(\"bank\")"]);
+        assert_eq!(
+            compile_error("tag:\"deep focus"),
+            (String::from("This quote is never closed"), 4, 15)
+        );
+        assert_eq!(
+            compile_error("\"  \""),
+            (String::from("There's nothing to search for here"), 0, 4)
+        );
+    }
+
+    #[test]
+    fn reports_where_filters_are_invalid() {
+        for (filter, message, start, end) in [
+            ("(tag:a", "This parenthesis is never closed", 0, 1),
+            ("tag:a)", "This parenthesis was never opened", 5, 6),
+            (")", "This parenthesis was never opened", 0, 1),
+            ("and tag:a", "Put a term before and", 0, 3),
+            (
+                "tag:a or",
+                "The filter ends where a term was expected",
+                8,
+                8,
+            ),
+            (
+                "foo:bar",
+                "There's no foo field. Try tag, project, due, start or priority",
+                0,
+                3,
+            ),
+            (":x", "Put a field like due or priority before :", 0, 1),
+            ("due<=", "Put a value after <=", 3, 5),
+            ("- x", "Put what to exclude right after -", 0, 1),
+        ] {
+            assert_eq!(
+                compile_error(filter),
+                (String::from(message), start, end),
+                "{}",
+                filter
+            );
+        }
+    }
+
+    #[test]
+    fn reports_spans_in_utf16() {
+        assert_eq!(
+            compile_error("café 😀 due:soon"),
+            (
+                String::from("soon isn't a date. Try YYYY-MM-DD, today, tomorrow or +3d"),
+                12,
+                16
+            )
+        );
+    }
+}
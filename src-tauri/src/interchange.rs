@@ -0,0 +1,191 @@
+//! Getting data into and out of the app in formats other programs can read.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::models::project;
+use crate::models::tag;
+use crate::models::todo_item;
+use crate::rank;
+use chrono::{DateTime, Utc};
+use json::{Document, ImportMode, ProjectRecord, TagRecord, TodoItemRecord, TodoItemTagRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+pub mod csv;
+pub mod ical;
+pub mod json;
+pub mod markdown;
+pub mod taskwarrior;
+pub mod todo_txt;
+
+/// The color of the tags and projects that imports create.
+const DEFAULT_COLOR: &str = "#808080";
+
+/// How many of each kind of thing an import wrote to the database.
+#[derive(Serialize, Deserialize, Debug, Default, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub todo_items: u32,
+    pub tags: u32,
+    pub projects: u32,
+    pub smart_lists: u32,
+}
+
+// Names match regardless of case, and spaces match underscores, since some
+// formats can't have spaces in names.
+fn name_key(name: &str) -> String {
+    return name
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join("_");
+}
+
+/// Collects items read from another format so they can be imported all at
+/// once. Tags and projects are referred to by name, and the ones that don't
+/// exist yet are created along with the items.
+pub struct NewItems {
+    document: Document,
+    tag_ids: HashMap<String, String>,
+    project_ids: HashMap<String, String>,
+    next_project_sort_order: i64,
+    last_position: Option<String>,
+}
+
+impl NewItems {
+    pub fn new(connection: &mut Connection, now: DateTime<Utc>) -> Result<NewItems, AppError> {
+        let mut tag_ids = HashMap::new();
+        let mut project_ids = HashMap::new();
+        let mut next_project_sort_order = 0;
+
+        for tag in tag::get_all(connection)? {
+            tag_ids.entry(name_key(&tag.name)).or_insert(tag.id);
+        }
+
+        for project in project::get_all(connection)? {
+            next_project_sort_order = next_project_sort_order.max(project.sort_order + 1);
+            project_ids
+                .entry(name_key(&project.name))
+                .or_insert(project.id);
+        }
+
+        return Ok(NewItems {
+            document: Document {
+                version: json::VERSION,
+                exported_at: now,
+                projects: Vec::new(),
+                tags: Vec::new(),
+                todo_items: Vec::new(),
+                todo_item_tags: Vec::new(),
+                smart_lists: Vec::new(),
+                settings: BTreeMap::new(),
+            },
+            tag_ids,
+            project_ids,
+            next_project_sort_order,
+            last_position: todo_item::get_last_position(connection)?,
+        });
+    }
+
+    pub fn project_id(&mut self, name: &str) -> String {
+        let document = &mut self.document;
+        let sort_order = &mut self.next_project_sort_order;
+
+        return self
+            .project_ids
+            .entry(name_key(name))
+            .or_insert_with(|| {
+                let id = Uuid::new_v4().to_string();
+
+                document.projects.push(ProjectRecord {
+                    id: id.clone(),
+                    name: String::from(name),
+                    color: String::from(DEFAULT_COLOR),
+                    icon: None,
+                    is_archived: false,
+                    sort_order: *sort_order,
+                });
+
+                *sort_order += 1;
+
+                id
+            })
+            .clone();
+    }
+
+    pub fn tag_id(&mut self, name: &str) -> String {
+        let document = &mut self.document;
+
+        return self
+            .tag_ids
+            .entry(name_key(name))
+            .or_insert_with(|| {
+                let id = Uuid::new_v4().to_string();
+
+                document.tags.push(TagRecord {
+                    id: id.clone(),
+                    name: String::from(name),
+                    color: String::from(DEFAULT_COLOR),
+                    deleted_at: None,
+                });
+
+                id
+            })
+            .clone();
+    }
+
+    /// Adds the item with the tags in `tag_ids`. Items without a position go
+    /// after everything added before them.
+    pub fn add(
+        &mut self,
+        mut todo_item: TodoItemRecord,
+        tag_ids: Vec<String>,
+    ) -> Result<(), AppError> {
+        if todo_item.position.is_empty() {
+            let position = rank::between(self.last_position.as_deref(), None)?;
+
+            todo_item.position = position.clone();
+            self.last_position = Some(position);
+        }
+
+        for tag_id in tag_ids {
+            self.document.todo_item_tags.push(TodoItemTagRecord {
+                todo_item_id: todo_item.id.clone(),
+                tag_id,
+            });
+        }
+
+        self.document.todo_items.push(todo_item);
+
+        Ok(())
+    }
+
+    /// Writes everything to the database in one savepoint. New tags are
+    /// created the way the app creates them, and the items refer to them
+    /// like to the tags that were there already.
+    pub fn import(
+        mut self,
+        connection: &mut Connection,
+        mode: ImportMode,
+    ) -> Result<ImportSummary, AppError> {
+        let tags = std::mem::take(&mut self.document.tags);
+
+        return connection.savepoint(|connection| {
+            for new_tag in &tags {
+                tag::create(
+                    connection,
+                    new_tag.id.clone(),
+                    new_tag.name.clone(),
+                    new_tag.color.clone(),
+                )?;
+            }
+
+            let mut summary = json::import_document(connection, self.document, mode)?;
+
+            summary.tags += tags.len() as u32;
+
+            return Ok(summary);
+        });
+    }
+}
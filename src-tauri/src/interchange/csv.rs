@@ -0,0 +1,706 @@
+//! CSV files from spreadsheets and other trackers, one item per row. Since
+//! every program names and orders its columns differently, the user says
+//! which column holds which field. `preview` reads the first rows of a file
+//! and guesses the columns from their names to start from, and `import` reads
+//! the whole file with the mapping the user settled on. Rows that can't be
+//! read are reported and left out instead of failing the whole import.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::interchange::json::{self, ImportMode, TodoItemRecord};
+use crate::interchange::{ImportSummary, NewItems};
+use crate::models::todo_item::Priority;
+use crate::recurrence::{RecurrenceMode, Rule};
+use crate::timestamp;
+use ::csv::{Reader, ReaderBuilder, Writer};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A field of a todo item that a column can hold.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Field {
+    Title,
+    Description,
+    Notes,
+    IsCompleted,
+    CreatedAt,
+    CompletedAt,
+    DueDate,
+    DueTime,
+    StartDate,
+    Priority,
+    Project,
+    /// A list of tag names separated by commas, semicolons or bars.
+    Tags,
+    /// An RRULE such as `FREQ=WEEKLY;INTERVAL=2`.
+    Recurrence,
+}
+
+const FIELDS: [Field; 13] = [
+    Field::Title,
+    Field::Description,
+    Field::Notes,
+    Field::IsCompleted,
+    Field::CreatedAt,
+    Field::CompletedAt,
+    Field::DueDate,
+    Field::DueTime,
+    Field::StartDate,
+    Field::Priority,
+    Field::Project,
+    Field::Tags,
+    Field::Recurrence,
+];
+
+impl Field {
+    /// The column name `export` writes.
+    fn label(self) -> &'static str {
+        match self {
+            Field::Title => "Title",
+            Field::Description => "Description",
+            Field::Notes => "Notes",
+            Field::IsCompleted => "Completed",
+            Field::CreatedAt => "Created at",
+            Field::CompletedAt => "Completed at",
+            Field::DueDate => "Due date",
+            Field::DueTime => "Due time",
+            Field::StartDate => "Start date",
+            Field::Priority => "Priority",
+            Field::Project => "Project",
+            Field::Tags => "Tags",
+            Field::Recurrence => "Recurrence",
+        }
+    }
+
+    // Other names that spreadsheets and trackers give the same column.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Field::Title => &["name", "task", "summary", "subject", "todo"],
+            Field::Description => &["details", "body"],
+            Field::Notes => &["note", "comment", "comments"],
+            Field::IsCompleted => &["done", "status", "complete", "is completed"],
+            Field::CreatedAt => &["created", "created date", "entry"],
+            Field::CompletedAt => &["completed date", "done at", "finished", "end"],
+            Field::DueDate => &["due", "due on", "deadline"],
+            Field::DueTime => &["time"],
+            Field::StartDate => &["start", "starts", "scheduled"],
+            Field::Priority => &["importance"],
+            Field::Project => &["list", "category", "folder"],
+            Field::Tags => &["tag", "label", "labels", "contexts"],
+            Field::Recurrence => &["repeat", "repeats", "rrule"],
+        }
+    }
+
+    fn is_date(self) -> bool {
+        return matches!(
+            self,
+            Field::CreatedAt | Field::CompletedAt | Field::DueDate | Field::StartDate
+        );
+    }
+}
+
+/// Which field a column holds, counting columns from 0.
+#[derive(Serialize, Deserialize, Debug, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    pub column: u32,
+    pub field: Field,
+}
+
+/// How to read a file. Columns that aren't mapped are ignored, and more than
+/// one column can hold tags.
+#[derive(Serialize, Deserialize, Debug, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvMapping {
+    pub columns: Vec<ColumnMapping>,
+    pub has_header: bool,
+    /// A format such as `%d/%m/%Y`, or none to detect it from the dates in
+    /// the file.
+    pub date_format: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvColumn {
+    pub index: u32,
+    pub name: String,
+    /// The field the column's name suggests it holds.
+    pub field: Option<Field>,
+}
+
+/// The start of a file, with a guess at how to read it for the user to
+/// correct before importing it.
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvPreview {
+    pub columns: Vec<CsvColumn>,
+    pub has_header: bool,
+    pub date_format: Option<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A row that was left out of an import, by its line in the file.
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvRowError {
+    pub line: u32,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvImportReport {
+    pub summary: ImportSummary,
+    pub errors: Vec<CsvRowError>,
+}
+
+// Ambiguous dates like 03/04/2024 are read month first, unless dates with a
+// day after the 12th show the file is day first.
+const DATE_FORMATS: [&str; 8] = [
+    "%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d/%m/%Y", "%d.%m.%Y", "%m-%d-%Y", "%d-%m-%Y", "%Y%m%d",
+];
+
+fn normalize_name(name: &str) -> String {
+    return name
+        .chars()
+        .filter(|character| character.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+}
+
+fn suggest_field(name: &str) -> Option<Field> {
+    let name = normalize_name(name);
+
+    if name.is_empty() {
+        return None;
+    }
+
+    return FIELDS.iter().copied().find(|field| {
+        normalize_name(field.label()) == name
+            || field
+                .aliases()
+                .iter()
+                .any(|alias| normalize_name(alias) == name)
+    });
+}
+
+// Spreadsheets in some locales separate columns with semicolons or tabs, since
+// they write decimals with commas.
+fn detect_delimiter(text: &str) -> u8 {
+    let first_line = text.lines().next().unwrap_or("");
+
+    return [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|delimiter| {
+            let count = first_line.matches(char::from(*delimiter)).count();
+
+            // Comma wins ties.
+            (count, *delimiter == b',')
+        })
+        .unwrap_or(b',');
+}
+
+// Spreadsheets often start the file with a byte order mark.
+fn read_text(path: &str) -> Result<String, AppError> {
+    let text = std::fs::read_to_string(path)?;
+
+    return Ok(match text.strip_prefix('\u{feff}') {
+        Some(text) => String::from(text),
+        None => text,
+    });
+}
+
+fn reader(text: &str) -> Reader<&[u8]> {
+    return ReaderBuilder::new()
+        .delimiter(detect_delimiter(text))
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+}
+
+// Dates can be followed by a time, which doesn't help tell formats apart.
+fn date_part(value: &str) -> &str {
+    return value.split([' ', 'T']).next().unwrap_or(value);
+}
+
+// The format that reads the most dates, so a few bad ones don't stop the
+// rest from being read right. Formats earlier in the list win ties.
+fn detect_date_format<'a>(values: impl Iterator<Item = &'a str>) -> Option<&'static str> {
+    let dates: Vec<&str> = values
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(date_part)
+        .collect();
+
+    let mut best = None;
+    let mut best_count = 0;
+
+    for format in DATE_FORMATS {
+        let count = dates
+            .iter()
+            .filter(|date| NaiveDate::parse_from_str(date, format).is_ok())
+            .count();
+
+        if count > best_count {
+            best = Some(format);
+            best_count = count;
+        }
+    }
+
+    return best;
+}
+
+// Without a format, each date is read with the first format that fits it.
+fn parse_date(value: &str, format: Option<&str>) -> Result<NaiveDate, AppError> {
+    let date = date_part(value);
+    let formats = match format {
+        Some(format) => vec![format],
+        None => DATE_FORMATS.to_vec(),
+    };
+
+    return formats
+        .into_iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+        .ok_or_else(|| AppError::Validation(format!("{} is not a date", value)));
+}
+
+// RFC 3339 timestamps are exact, and anything else is a date and maybe a time
+// in `timezone`.
+fn parse_instant(
+    value: &str,
+    format: Option<&str>,
+    timezone: Tz,
+) -> Result<DateTime<Utc>, AppError> {
+    if let Ok(instant) = timestamp::parse_instant(value) {
+        return Ok(instant);
+    }
+
+    let date = parse_date(value, format)?;
+    let time = value
+        .split_once([' ', 'T'])
+        .map(|(_, time)| timestamp::parse_time(time.trim()))
+        .transpose()?;
+
+    return Ok(
+        match time.and_then(|time| {
+            timezone
+                .from_local_datetime(&date.and_time(time))
+                .earliest()
+        }) {
+            Some(instant) => instant.with_timezone(&Utc),
+            None => timestamp::start_of_day(date, timezone),
+        },
+    );
+}
+
+fn parse_completed(value: &str) -> Result<bool, AppError> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" | "done" | "complete" | "completed" | "closed" => Ok(true),
+        "false" | "no" | "n" | "0" | "todo" | "open" | "pending" | "needs-action" => Ok(false),
+        _ => Err(AppError::Validation(format!("{} is not yes or no", value))),
+    }
+}
+
+fn format_priority(priority: Priority) -> &'static str {
+    match priority {
+        Priority::None => "",
+        Priority::Low => "low",
+        Priority::Medium => "medium",
+        Priority::High => "high",
+        Priority::Urgent => "urgent",
+    }
+}
+
+fn parse_priority(value: &str) -> Result<Priority, AppError> {
+    match value.to_lowercase().as_str() {
+        "none" | "0" => Ok(Priority::None),
+        "low" | "1" => Ok(Priority::Low),
+        "medium" | "normal" | "2" => Ok(Priority::Medium),
+        "high" | "3" => Ok(Priority::High),
+        "urgent" | "4" => Ok(Priority::Urgent),
+        _ => Err(AppError::Validation(format!("{} is not a priority", value))),
+    }
+}
+
+fn split_tags(value: &str) -> impl Iterator<Item = &str> {
+    return value
+        .split([',', ';', '|'])
+        .map(|name| name.trim().trim_start_matches('#'))
+        .filter(|name| !name.is_empty());
+}
+
+/// Reads up to `limit` rows of the file at `path`, along with its columns and
+/// the fields their names suggest. The file is taken to have a header if any
+/// of its first row's names suggest a field.
+pub fn preview(path: &str, limit: u32) -> Result<CsvPreview, AppError> {
+    let text = read_text(path)?;
+    let limit = limit as usize;
+
+    let mut rows = Vec::new();
+
+    for record in reader(&text).into_records().take(limit + 1) {
+        rows.push(record?.iter().map(String::from).collect::<Vec<String>>());
+    }
+
+    let first_row = rows.first().cloned().unwrap_or_default();
+    let mut fields: Vec<Option<Field>> = first_row.iter().map(|name| suggest_field(name)).collect();
+
+    // Only tags can be in more than one column.
+    for index in 0..fields.len() {
+        if fields[..index].contains(&fields[index]) && fields[index] != Some(Field::Tags) {
+            fields[index] = None;
+        }
+    }
+
+    let has_header = fields.iter().any(Option::is_some);
+
+    if has_header {
+        rows.remove(0);
+    } else {
+        rows.truncate(limit);
+    }
+
+    let width = rows
+        .iter()
+        .map(Vec::len)
+        .chain([first_row.len()])
+        .max()
+        .unwrap_or(0);
+
+    let columns: Vec<CsvColumn> = (0..width)
+        .map(|index| CsvColumn {
+            index: index as u32,
+            name: if has_header {
+                first_row.get(index).cloned().unwrap_or_default()
+            } else {
+                format!("Column {}", index + 1)
+            },
+            field: if has_header {
+                fields.get(index).copied().flatten()
+            } else {
+                None
+            },
+        })
+        .collect();
+
+    let date_format = detect_date_format(
+        columns
+            .iter()
+            .filter(|column| column.field.map_or(false, Field::is_date))
+            .flat_map(|column| {
+                rows.iter()
+                    .filter_map(move |row| row.get(column.index as usize))
+            })
+            .map(String::as_str),
+    );
+
+    return Ok(CsvPreview {
+        columns,
+        has_header,
+        date_format: date_format.map(String::from),
+        rows,
+    });
+}
+
+fn validate_mapping(mapping: &CsvMapping) -> Result<(), AppError> {
+    if !mapping
+        .columns
+        .iter()
+        .any(|column| column.field == Field::Title)
+    {
+        return Err(AppError::Validation(String::from(
+            "One of the columns has to be the title",
+        )));
+    }
+
+    for (index, column) in mapping.columns.iter().enumerate() {
+        let is_repeated = mapping.columns[..index]
+            .iter()
+            .any(|other| other.field == column.field);
+
+        if is_repeated && column.field != Field::Tags {
+            return Err(AppError::Validation(format!(
+                "{} can only be in one column",
+                column.field.label()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// Projects and tags are only created once the whole row has been read, so a
+// row that's left out doesn't leave them behind.
+fn parse_row(
+    items: &mut NewItems,
+    mapping: &CsvMapping,
+    row: &[String],
+    date_format: Option<&str>,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let mut todo_item = TodoItemRecord::new(String::new(), now, timezone);
+    let mut is_completed = None;
+    let mut project = None;
+    let mut tag_names = Vec::new();
+
+    for column in &mapping.columns {
+        let value = row
+            .get(column.column as usize)
+            .map_or("", |value| value.trim());
+
+        if value.is_empty() {
+            continue;
+        }
+
+        match column.field {
+            Field::Title => todo_item.title = String::from(value),
+            Field::Description => todo_item.description = Some(String::from(value)),
+            Field::Notes => todo_item.notes = Some(String::from(value)),
+            Field::IsCompleted => is_completed = Some(parse_completed(value)?),
+            Field::CreatedAt => todo_item.created_at = parse_instant(value, date_format, timezone)?,
+            Field::CompletedAt => {
+                todo_item.completed_at = Some(parse_instant(value, date_format, timezone)?)
+            }
+            Field::DueDate => todo_item.due_date = Some(parse_date(value, date_format)?),
+            Field::DueTime => todo_item.due_time = Some(timestamp::parse_time(value)?),
+            Field::StartDate => todo_item.start_date = Some(parse_date(value, date_format)?),
+            Field::Priority => todo_item.priority = parse_priority(value)?,
+            Field::Project => project = Some(value),
+            Field::Tags => tag_names.extend(split_tags(value)),
+            Field::Recurrence => todo_item.recurrence = Some(Rule::parse(value)?.to_string()),
+        }
+    }
+
+    if todo_item.title.is_empty() {
+        return Err(AppError::Validation(String::from("The row has no title")));
+    }
+
+    if todo_item.due_time.is_some() && todo_item.due_date.is_none() {
+        return Err(AppError::Validation(String::from(
+            "The row has a due time but no due date",
+        )));
+    }
+
+    // A completion date is enough to say the item was done.
+    todo_item.is_completed = is_completed.unwrap_or(todo_item.completed_at.is_some());
+
+    if todo_item.is_completed {
+        todo_item.completed_at.get_or_insert(now);
+        todo_item.completed_timezone = Some(String::from(timezone.name()));
+    } else {
+        todo_item.completed_at = None;
+    }
+
+    if todo_item.recurrence.is_some() {
+        todo_item.recurrence_mode = Some(RecurrenceMode::FromDueDate);
+        todo_item.series_id = Some(todo_item.id.clone());
+        todo_item.occurrence = Some(1);
+    }
+
+    todo_item.project_id = project.map(|project| items.project_id(project));
+
+    let tag_ids = tag_names
+        .into_iter()
+        .map(|tag_name| items.tag_id(tag_name))
+        .collect();
+
+    return items.add(todo_item, tag_ids);
+}
+
+/// Adds the rows of the file at `path` after the items already in the
+/// database, reading them as `mapping` says. Dates without a time start at
+/// midnight in `timezone`. The rows that can't be read are left out and
+/// reported by line, and the rest are imported.
+pub fn import(
+    connection: &mut Connection,
+    path: &str,
+    mapping: &CsvMapping,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<CsvImportReport, AppError> {
+    validate_mapping(mapping)?;
+
+    let text = read_text(path)?;
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, record) in reader(&text).into_records().enumerate() {
+        if index == 0 && mapping.has_header {
+            continue;
+        }
+
+        match record {
+            Ok(record) => rows.push((
+                record
+                    .position()
+                    .map_or(0, |position| position.line() as u32),
+                record.iter().map(String::from).collect::<Vec<String>>(),
+            )),
+            Err(error) => errors.push(CsvRowError {
+                line: error
+                    .position()
+                    .map_or(0, |position| position.line() as u32),
+                message: error.to_string(),
+            }),
+        }
+    }
+
+    let date_format = match &mapping.date_format {
+        Some(date_format) => Some(date_format.clone()),
+        None => detect_date_format(
+            mapping
+                .columns
+                .iter()
+                .filter(|column| column.field.is_date())
+                .flat_map(|column| {
+                    rows.iter()
+                        .filter_map(move |(_, row)| row.get(column.column as usize))
+                })
+                .map(String::as_str),
+        )
+        .map(String::from),
+    };
+
+    let mut items = NewItems::new(connection, now)?;
+
+    for (line, row) in &rows {
+        if row.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+
+        match parse_row(
+            &mut items,
+            mapping,
+            row,
+            date_format.as_deref(),
+            timezone,
+            now,
+        ) {
+            Ok(()) => {}
+            Err(AppError::Validation(message)) => errors.push(CsvRowError {
+                line: *line,
+                message,
+            }),
+            Err(error) => return Err(error),
+        }
+    }
+
+    errors.sort_by_key(|error| error.line);
+
+    return Ok(CsvImportReport {
+        summary: items.import(connection, ImportMode::DuplicateAsNew)?,
+        errors,
+    });
+}
+
+fn format_field(
+    field: Field,
+    todo_item: &TodoItemRecord,
+    project: Option<&str>,
+    tags: &[&str],
+) -> String {
+    match field {
+        Field::Title => todo_item.title.clone(),
+        Field::Description => todo_item.description.clone().unwrap_or_default(),
+        Field::Notes => todo_item.notes.clone().unwrap_or_default(),
+        Field::IsCompleted => todo_item.is_completed.to_string(),
+        Field::CreatedAt => timestamp::to_sql(&todo_item.created_at),
+        Field::CompletedAt => todo_item
+            .completed_at
+            .as_ref()
+            .map(timestamp::to_sql)
+            .unwrap_or_default(),
+        Field::DueDate => todo_item
+            .due_date
+            .as_ref()
+            .map(timestamp::date_to_sql)
+            .unwrap_or_default(),
+        Field::DueTime => todo_item
+            .due_time
+            .as_ref()
+            .map(timestamp::time_to_sql)
+            .unwrap_or_default(),
+        Field::StartDate => todo_item
+            .start_date
+            .as_ref()
+            .map(timestamp::date_to_sql)
+            .unwrap_or_default(),
+        Field::Priority => String::from(format_priority(todo_item.priority)),
+        Field::Project => String::from(project.unwrap_or("")),
+        Field::Tags => tags.join(", "),
+        Field::Recurrence => todo_item.recurrence.clone().unwrap_or_default(),
+    }
+}
+
+/// Writes every item that isn't in the trash to the file at `path`, in order,
+/// with a header and one column for each of `fields`.
+pub fn export(
+    connection: &mut Connection,
+    path: &str,
+    fields: &[Field],
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    if fields.is_empty() {
+        return Err(AppError::Validation(String::from(
+            "Choose at least one column to export",
+        )));
+    }
+
+    let document = json::read(connection, now)?;
+
+    let projects: HashMap<&str, &str> = document
+        .projects
+        .iter()
+        .map(|project| (project.id.as_str(), project.name.as_str()))
+        .collect();
+    let tags: HashMap<&str, &str> = document
+        .tags
+        .iter()
+        .filter(|tag| tag.deleted_at.is_none())
+        .map(|tag| (tag.id.as_str(), tag.name.as_str()))
+        .collect();
+
+    let mut item_tags: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for todo_item_tag in &document.todo_item_tags {
+        if let Some(tag) = tags.get(todo_item_tag.tag_id.as_str()).copied() {
+            item_tags
+                .entry(todo_item_tag.todo_item_id.as_str())
+                .or_default()
+                .push(tag);
+        }
+    }
+
+    let mut writer = Writer::from_path(path)?;
+
+    writer.write_record(fields.iter().map(|field| field.label()))?;
+
+    for todo_item in &document.todo_items {
+        if todo_item.deleted_at.is_some() {
+            continue;
+        }
+
+        let project = todo_item
+            .project_id
+            .as_deref()
+            .and_then(|project_id| projects.get(project_id).copied());
+        let mut tag_names = item_tags.remove(todo_item.id.as_str()).unwrap_or_default();
+
+        tag_names.sort_by_key(|tag_name| tag_name.to_lowercase());
+
+        writer.write_record(
+            fields
+                .iter()
+                .map(|field| format_field(*field, todo_item, project, &tag_names)),
+        )?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
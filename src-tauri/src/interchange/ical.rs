@@ -0,0 +1,766 @@
+//! iCalendar files (RFC 5545) holding one VTODO per item, which calendar apps
+//! can import or subscribe to:
+//!
+//! ```text
+//! BEGIN:VTODO
+//! UID:5f0c6a44-2d4b-4c1e-9a51-0f1fd2f8e3b7
+//! DTSTAMP:20240301T120000Z
+//! SUMMARY:Water the plants
+//! STATUS:NEEDS-ACTION
+//! DUE;VALUE=DATE:20240308
+//! RRULE:FREQ=WEEKLY
+//! CATEGORIES:Garden,Home
+//! END:VTODO
+//! ```
+//!
+//! The UID is the item's id, so importing a file the app wrote updates the
+//! items in it instead of adding copies. Due and start dates with a time are
+//! floating, meaning they're in whatever timezone the calendar is in, as they
+//! are in the app. Tags become CATEGORIES and are matched by name, and a
+//! parent becomes RELATED-TO. Projects, notes and positions have no place in
+//! a VTODO, so imported items keep the ones they have, and new ones go at the
+//! end without a project.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::interchange::json::{self, Document, ImportMode, TodoItemRecord};
+use crate::interchange::{ImportSummary, NewItems};
+use crate::models::setting;
+use crate::models::todo_item::Priority;
+use crate::recurrence::{RecurrenceMode, Rule};
+use crate::timestamp;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+const PRODUCT_ID: &str = "-//get-tasking//get-tasking//EN";
+
+/// Lines longer than this many bytes are folded onto the next line.
+const LINE_LENGTH: usize = 75;
+
+/// The name of the feed file in the app data directory.
+pub const FEED_FILE_NAME: &str = "feed.ics";
+
+/// How long the feed waits before trying a refresh that failed again.
+pub const FEED_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The longest the feed waits between retries while refreshes keep failing.
+pub const FEED_MAX_RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Whether the feed is kept up to date, and where calendar apps can find it.
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedStatus {
+    pub enabled: bool,
+    pub path: String,
+}
+
+fn priority_to_ical(priority: Priority) -> Option<u8> {
+    match priority {
+        Priority::None => None,
+        Priority::Low => Some(9),
+        Priority::Medium => Some(5),
+        Priority::High => Some(3),
+        Priority::Urgent => Some(1),
+    }
+}
+
+// 1 is the most urgent and 9 the least, with 0 meaning no priority at all.
+fn priority_from_ical(value: &str) -> Result<Priority, AppError> {
+    match value.trim().parse::<u8>() {
+        Ok(0) => Ok(Priority::None),
+        Ok(1) => Ok(Priority::Urgent),
+        Ok(2..=4) => Ok(Priority::High),
+        Ok(5) => Ok(Priority::Medium),
+        Ok(6..=9) => Ok(Priority::Low),
+        _ => Err(AppError::Validation(format!(
+            "{} is not a priority from 0 to 9",
+            value
+        ))),
+    }
+}
+
+fn escape_text(value: &str) -> String {
+    return value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n");
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::new();
+    let mut characters = value.chars();
+
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            text.push(character);
+            continue;
+        }
+
+        match characters.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => text.push('\\'),
+        }
+    }
+
+    return text;
+}
+
+// Splits a list of text values on the commas that aren't escaped.
+fn split_text_list(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut is_escaped = false;
+
+    for (index, character) in value.char_indices() {
+        match character {
+            _ if is_escaped => is_escaped = false,
+            '\\' => is_escaped = true,
+            ',' => {
+                values.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    values.push(&value[start..]);
+
+    return values
+        .into_iter()
+        .map(|value| unescape_text(value).trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+}
+
+fn format_instant(instant: &DateTime<Utc>) -> String {
+    return instant.format("%Y%m%dT%H%M%SZ").to_string();
+}
+
+fn format_date(date: &NaiveDate) -> String {
+    return date.format("%Y%m%d").to_string();
+}
+
+// Folds on character boundaries, so a multibyte character is never split
+// across lines.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for character in line.chars() {
+        if length + character.len_utf8() > LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+
+        folded.push(character);
+        length += character.len_utf8();
+    }
+
+    return folded;
+}
+
+fn format_todo(
+    todo_item: &TodoItemRecord,
+    tags: &[&str],
+    now: DateTime<Utc>,
+) -> Result<Vec<String>, AppError> {
+    let mut lines = vec![
+        String::from("BEGIN:VTODO"),
+        format!("UID:{}", todo_item.id),
+        format!("DTSTAMP:{}", format_instant(&now)),
+        format!("CREATED:{}", format_instant(&todo_item.created_at)),
+        format!("SUMMARY:{}", escape_text(&todo_item.title)),
+    ];
+
+    if let Some(description) = &todo_item.description {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+
+    if todo_item.is_completed {
+        lines.push(String::from("STATUS:COMPLETED"));
+
+        if let Some(completed_at) = &todo_item.completed_at {
+            lines.push(format!("COMPLETED:{}", format_instant(completed_at)));
+        }
+    } else {
+        lines.push(String::from("STATUS:NEEDS-ACTION"));
+    }
+
+    // DTSTART has to be the same kind of value as DUE, so it starts at
+    // midnight when the item is due at a time.
+    if let Some(start_date) = &todo_item.start_date {
+        lines.push(match todo_item.due_time {
+            Some(_) => format!("DTSTART:{}T000000", format_date(start_date)),
+            None => format!("DTSTART;VALUE=DATE:{}", format_date(start_date)),
+        });
+    }
+
+    if let Some(due_date) = &todo_item.due_date {
+        lines.push(match &todo_item.due_time {
+            Some(due_time) => format!(
+                "DUE:{}T{}",
+                format_date(due_date),
+                due_time.format("%H%M%S")
+            ),
+            None => format!("DUE;VALUE=DATE:{}", format_date(due_date)),
+        });
+    }
+
+    if let Some(recurrence) = &todo_item.recurrence {
+        lines.push(format!("RRULE:{}", Rule::parse(recurrence)?));
+    }
+
+    if let Some(priority) = priority_to_ical(todo_item.priority) {
+        lines.push(format!("PRIORITY:{}", priority));
+    }
+
+    if !tags.is_empty() {
+        let categories: Vec<String> = tags.iter().map(|tag| escape_text(tag)).collect();
+
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+
+    if let Some(parent_id) = &todo_item.parent_id {
+        lines.push(format!("RELATED-TO:{}", parent_id));
+    }
+
+    lines.push(String::from("END:VTODO"));
+
+    return Ok(lines);
+}
+
+fn format_calendar(document: &Document, now: DateTime<Utc>) -> Result<String, AppError> {
+    let tags: HashMap<&str, &str> = document
+        .tags
+        .iter()
+        .filter(|tag| tag.deleted_at.is_none())
+        .map(|tag| (tag.id.as_str(), tag.name.as_str()))
+        .collect();
+
+    let mut item_tags: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for todo_item_tag in &document.todo_item_tags {
+        if let Some(tag) = tags.get(todo_item_tag.tag_id.as_str()).copied() {
+            item_tags
+                .entry(todo_item_tag.todo_item_id.as_str())
+                .or_default()
+                .push(tag);
+        }
+    }
+
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:{}", PRODUCT_ID),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+
+    for todo_item in &document.todo_items {
+        if todo_item.deleted_at.is_some() {
+            continue;
+        }
+
+        let mut tag_names = item_tags.remove(todo_item.id.as_str()).unwrap_or_default();
+
+        tag_names.sort_by_key(|tag_name| tag_name.to_lowercase());
+
+        lines.extend(format_todo(todo_item, &tag_names, now)?);
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    let mut text = lines
+        .iter()
+        .map(|line| fold(line))
+        .collect::<Vec<String>>()
+        .join("\r\n");
+    text.push_str("\r\n");
+
+    return Ok(text);
+}
+
+/// Writes every item that isn't in the trash to the file at `path`, in order.
+pub fn export(connection: &mut Connection, path: &str, now: DateTime<Utc>) -> Result<(), AppError> {
+    let document = json::read(connection, now)?;
+
+    std::fs::write(path, format_calendar(&document, now)?)?;
+
+    Ok(())
+}
+
+struct Property {
+    name: String,
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parameter(&self, name: &str) -> Option<&str> {
+        return self
+            .parameters
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| value.as_str());
+    }
+}
+
+// Lines that start with a space or a tab continue the line before them.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        match (
+            line.strip_prefix(|c| c == ' ' || c == '\t'),
+            lines.last_mut(),
+        ) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if line.is_empty() => {}
+            _ => lines.push(String::from(line)),
+        }
+    }
+
+    return lines;
+}
+
+// Parameter values can be quoted, in which case they can hold the `;` and `:`
+// that otherwise end them.
+fn parse_property(line: &str) -> Result<Property, AppError> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut is_quoted = false;
+
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => is_quoted = !is_quoted,
+            ';' if !is_quoted => {
+                parts.push(&line[start..index]);
+                start = index + 1;
+            }
+            ':' if !is_quoted => {
+                parts.push(&line[start..index]);
+
+                let parameters = parts[1..]
+                    .iter()
+                    .map(|parameter| {
+                        let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+
+                        (
+                            name.to_ascii_uppercase(),
+                            value.trim_matches('"').to_string(),
+                        )
+                    })
+                    .collect();
+
+                return Ok(Property {
+                    name: parts[0].to_ascii_uppercase(),
+                    parameters,
+                    value: String::from(&line[index + 1..]),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Err(AppError::Validation(format!(
+        "{} is not an iCalendar property",
+        line
+    )))
+}
+
+// Returns the properties of each VTODO. Components inside a VTODO, such as
+// alarms, are skipped along with everything that isn't a VTODO.
+fn parse_calendar(text: &str) -> Result<Vec<Vec<Property>>, AppError> {
+    let lines = unfold(text);
+
+    let is_calendar = lines.first().map_or(false, |line| {
+        line.trim_end().eq_ignore_ascii_case("BEGIN:VCALENDAR")
+    });
+
+    if !is_calendar {
+        return Err(AppError::Validation(String::from(
+            "The file is not an iCalendar file",
+        )));
+    }
+
+    let mut todos = Vec::new();
+    let mut todo: Option<Vec<Property>> = None;
+    let mut depth = 0;
+
+    for line in lines {
+        let property = parse_property(&line)?;
+        let is_todo = property.value.trim().eq_ignore_ascii_case("VTODO");
+
+        match todo.as_mut() {
+            None => {
+                if property.name == "BEGIN" && is_todo {
+                    todo = Some(Vec::new());
+                }
+            }
+            Some(properties) => match property.name.as_str() {
+                "BEGIN" => depth += 1,
+                "END" if depth > 0 => depth -= 1,
+                "END" => todos.extend(todo.take()),
+                _ if depth == 0 => properties.push(property),
+                _ => {}
+            },
+        }
+    }
+
+    if todo.is_some() {
+        return Err(AppError::Validation(String::from(
+            "A VTODO is missing its END",
+        )));
+    }
+
+    return Ok(todos);
+}
+
+enum Moment {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Instant(DateTime<Utc>),
+}
+
+// Times are in UTC when they end in `Z`, in the TZID parameter's timezone when
+// it has one the app knows, and floating otherwise.
+fn parse_moment(property: &Property) -> Result<Moment, AppError> {
+    let value = property.value.trim();
+    let invalid = || AppError::Validation(format!("{} is not an iCalendar date", value));
+
+    if property.parameter("VALUE") == Some("DATE") || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(Moment::Date)
+            .map_err(|_| invalid());
+    }
+
+    let (local, is_utc) = match value.strip_suffix(|c| c == 'Z' || c == 'z') {
+        Some(local) => (local, true),
+        None => (value, false),
+    };
+    let local = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+
+    if is_utc {
+        return Ok(Moment::Instant(Utc.from_utc_datetime(&local)));
+    }
+
+    match property
+        .parameter("TZID")
+        .and_then(|timezone| timezone.parse::<Tz>().ok())
+    {
+        Some(timezone) => Ok(Moment::Instant(
+            timezone
+                .from_local_datetime(&local)
+                .earliest()
+                .ok_or_else(invalid)?
+                .with_timezone(&Utc),
+        )),
+        None => Ok(Moment::Floating(local)),
+    }
+}
+
+// Floating times and dates are in `timezone`.
+fn parse_instant(property: &Property, timezone: Tz) -> Result<DateTime<Utc>, AppError> {
+    match parse_moment(property)? {
+        Moment::Date(date) => Ok(timestamp::start_of_day(date, timezone)),
+        Moment::Floating(local) => timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|instant| instant.with_timezone(&Utc))
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "{} does not exist in {}",
+                    property.value,
+                    timezone.name()
+                ))
+            }),
+        Moment::Instant(instant) => Ok(instant),
+    }
+}
+
+// Times in a timezone are moved to `timezone`, while floating ones already are
+// wherever the user is.
+fn parse_local(property: &Property, timezone: Tz) -> Result<NaiveDateTime, AppError> {
+    match parse_moment(property)? {
+        Moment::Date(date) => Ok(date.and_hms_opt(0, 0, 0).unwrap()),
+        Moment::Floating(local) => Ok(local),
+        Moment::Instant(instant) => Ok(instant.with_timezone(&timezone).naive_local()),
+    }
+}
+
+// Updates the item with the same UID if there is one. Everything a VTODO can
+// say about an item is taken from it, so a property that's missing is cleared.
+fn parse_todo(
+    items: &mut NewItems,
+    existing: &mut HashMap<String, TodoItemRecord>,
+    ids: &HashSet<String>,
+    properties: &[Property],
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let find = |name: &str| properties.iter().find(|property| property.name == name);
+
+    let uid = find("UID")
+        .map(|property| property.value.trim().to_string())
+        .filter(|uid| !uid.is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let title = find("SUMMARY")
+        .map(|property| unescape_text(&property.value).trim().to_string())
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| AppError::Validation(format!("Todo {} has no SUMMARY", uid)))?;
+
+    let mut todo_item = match existing.remove(&uid) {
+        Some(todo_item) => todo_item,
+        None => {
+            let created_at = match find("CREATED") {
+                Some(property) => parse_instant(property, timezone)?,
+                None => now,
+            };
+            let mut todo_item = TodoItemRecord::new(title.clone(), created_at, timezone);
+
+            todo_item.id = uid.clone();
+            todo_item
+        }
+    };
+
+    todo_item.title = title;
+    todo_item.description = find("DESCRIPTION")
+        .map(|property| unescape_text(&property.value))
+        .filter(|description| !description.trim().is_empty());
+
+    let completed = find("COMPLETED");
+    todo_item.is_completed = match find("STATUS") {
+        Some(status) => status.value.trim().eq_ignore_ascii_case("COMPLETED"),
+        None => completed.is_some(),
+    };
+
+    // Times in the file only have seconds, so an item completed when the file
+    // says it was keeps the exact time it has.
+    match (todo_item.is_completed, completed) {
+        (true, Some(completed)) => {
+            let completed_at = parse_instant(completed, timezone)?;
+
+            if todo_item.completed_at.map(|instant| instant.timestamp())
+                != Some(completed_at.timestamp())
+            {
+                todo_item.completed_at = Some(completed_at);
+                todo_item.completed_timezone = Some(String::from(timezone.name()));
+            }
+        }
+        (true, None) => {
+            if todo_item.completed_at.is_none() {
+                todo_item.completed_at = Some(now);
+                todo_item.completed_timezone = Some(String::from(timezone.name()));
+            }
+        }
+        (false, _) => {
+            todo_item.completed_at = None;
+            todo_item.completed_timezone = None;
+        }
+    }
+
+    match find("DUE") {
+        Some(property) => {
+            let is_date = matches!(parse_moment(property)?, Moment::Date(_));
+            let due = parse_local(property, timezone)?;
+
+            todo_item.due_date = Some(due.date());
+            todo_item.due_time = if is_date { None } else { Some(due.time()) };
+        }
+        None => {
+            todo_item.due_date = None;
+            todo_item.due_time = None;
+        }
+    }
+
+    todo_item.start_date = match find("DTSTART") {
+        Some(property) => Some(parse_local(property, timezone)?.date()),
+        None => None,
+    };
+
+    // A calendar repeats on a fixed schedule, however late the last
+    // occurrence was done.
+    match find("RRULE") {
+        Some(property) => {
+            todo_item.recurrence = Some(Rule::parse(&property.value)?.to_string());
+            todo_item
+                .recurrence_mode
+                .get_or_insert(RecurrenceMode::FromDueDate);
+            todo_item.series_id.get_or_insert_with(|| uid.clone());
+            todo_item.occurrence.get_or_insert(1);
+        }
+        None => {
+            todo_item.recurrence = None;
+            todo_item.recurrence_mode = None;
+        }
+    }
+
+    todo_item.priority = match find("PRIORITY") {
+        Some(property) => priority_from_ical(&property.value)?,
+        None => Priority::None,
+    };
+
+    // Only parents that are in the file or the database can be kept.
+    todo_item.parent_id = properties
+        .iter()
+        .filter(|property| property.name == "RELATED-TO")
+        .find(|property| {
+            property
+                .parameter("RELTYPE")
+                .map_or(true, |relation| relation.eq_ignore_ascii_case("PARENT"))
+        })
+        .map(|property| property.value.trim().to_string())
+        .filter(|parent_id| *parent_id != uid && ids.contains(parent_id));
+
+    let tag_ids = properties
+        .iter()
+        .filter(|property| property.name == "CATEGORIES")
+        .flat_map(|property| split_text_list(&property.value))
+        .map(|tag_name| items.tag_id(&tag_name))
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+
+    return items.add(todo_item, tag_ids);
+}
+
+/// Reads the VTODOs in the file at `path` into the database. Items whose UID
+/// is already there are updated, and the rest are added after the items that
+/// are, creating the tags they mention that don't exist yet.
+pub fn import(
+    connection: &mut Connection,
+    path: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<ImportSummary, AppError> {
+    let todos = parse_calendar(&std::fs::read_to_string(path)?)?;
+
+    let mut existing: HashMap<String, TodoItemRecord> = json::read(connection, now)?
+        .todo_items
+        .into_iter()
+        .map(|todo_item| (todo_item.id.clone(), todo_item))
+        .collect();
+
+    let mut ids: HashSet<String> = existing.keys().cloned().collect();
+
+    for properties in &todos {
+        for property in properties {
+            if property.name == "UID" {
+                ids.insert(property.value.trim().to_string());
+            }
+        }
+    }
+
+    let mut items = NewItems::new(connection, now)?;
+
+    for (index, properties) in todos.iter().enumerate() {
+        parse_todo(&mut items, &mut existing, &ids, properties, timezone, now).map_err(
+            |error| match error {
+                AppError::Validation(message) => {
+                    AppError::Validation(format!("Todo {}: {}", index + 1, message))
+                }
+                error => error,
+            },
+        )?;
+    }
+
+    return items.import(connection, ImportMode::MergeById);
+}
+
+pub fn is_feed_enabled(connection: &mut Connection) -> Result<bool, AppError> {
+    let value = setting::get(connection, setting::ICAL_FEED_ENABLED)?;
+
+    match value.as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(AppError::Database(format!(
+            "Invalid calendar feed setting {} in database",
+            value
+        ))),
+    }
+}
+
+pub fn set_feed_enabled(connection: &mut Connection, enabled: bool) -> Result<(), AppError> {
+    setting::set(
+        connection,
+        setting::ICAL_FEED_ENABLED,
+        if enabled { "true" } else { "false" },
+    )?;
+
+    connection.notify_written();
+
+    Ok(())
+}
+
+pub fn get_feed_status(
+    connection: &mut Connection,
+    directory: &Path,
+) -> Result<FeedStatus, AppError> {
+    return Ok(FeedStatus {
+        enabled: is_feed_enabled(connection)?,
+        path: directory.join(FEED_FILE_NAME).display().to_string(),
+    });
+}
+
+/// The feed file in the app data directory, which calendar apps can subscribe
+/// to while the feed setting is on.
+pub struct Feed {
+    path: PathBuf,
+    total_changes: Option<i64>,
+}
+
+impl Feed {
+    pub fn new(directory: &Path) -> Feed {
+        return Feed {
+            path: directory.join(FEED_FILE_NAME),
+            total_changes: None,
+        };
+    }
+
+    /// Rewrites the feed if anything was written to the database since it was
+    /// last written, and deletes it while the feed is turned off.
+    pub fn refresh(
+        &mut self,
+        connection: &mut Connection,
+        now: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        if !is_feed_enabled(connection)? {
+            self.total_changes = None;
+
+            if self.path.exists() {
+                std::fs::remove_file(&self.path)?;
+            }
+
+            return Ok(());
+        }
+
+        let total_changes = connection.total_changes()?;
+
+        if self.total_changes == Some(total_changes) {
+            return Ok(());
+        }
+
+        let text = format_calendar(&json::read(connection, now)?, now)?;
+
+        // Calendar apps could read the feed halfway through writing it, so it
+        // is written beside it and then moved over it.
+        let temporary_path = self.path.with_extension("ics.tmp");
+
+        std::fs::write(&temporary_path, text)?;
+        std::fs::rename(&temporary_path, &self.path)?;
+
+        self.total_changes = Some(total_changes);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,974 @@
+//! A versioned JSON document holding everything the user has made: todo
+//! items, tags and which items have them, projects, smart lists and settings.
+//! Item history, the undo journal and the search index aren't part of it,
+//! since the database keeps them up to date on its own.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::interchange::ical;
+use crate::interchange::ImportSummary;
+use crate::journal;
+use crate::models::setting;
+use crate::models::smart_list;
+use crate::models::todo_item::{self, Priority};
+use crate::models::trash;
+use crate::rank;
+use crate::recurrence::{RecurrenceMode, Rule};
+use crate::timestamp;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlite::State;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// The version of the document that `export` writes. It has to go up
+/// whenever the document changes in a way older versions of the app couldn't
+/// read.
+pub const VERSION: u32 = 1;
+
+/// What to do with what's already in the database.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// Deletes everything first, leaving exactly what's in the file.
+    Replace,
+    /// Keeps everything, overwriting whatever has the same id as something
+    /// in the file. Items in the file end up with only the tags it gives them.
+    MergeById,
+    /// Keeps everything and adds a copy of what's in the file under new ids.
+    DuplicateAsNew,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRecord {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub icon: Option<String>,
+    pub is_archived: bool,
+    pub sort_order: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRecord {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItemRecord {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub notes: Option<String>,
+    pub is_completed: bool,
+    pub created_at: DateTime<Utc>,
+    pub created_timezone: String,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub completed_timezone: Option<String>,
+    pub due_date: Option<NaiveDate>,
+    pub due_time: Option<NaiveTime>,
+    pub start_date: Option<NaiveDate>,
+    pub recurrence: Option<String>,
+    pub recurrence_mode: Option<RecurrenceMode>,
+    pub series_id: Option<String>,
+    pub occurrence: Option<u32>,
+    pub parent_id: Option<String>,
+    pub completes_with_children: bool,
+    pub project_id: Option<String>,
+    pub position: String,
+    pub priority: Priority,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl TodoItemRecord {
+    /// An item that hasn't been done, with nothing set but its title. It
+    /// still needs a position before it can be imported.
+    pub fn new(title: String, created_at: DateTime<Utc>, created_timezone: Tz) -> TodoItemRecord {
+        return TodoItemRecord {
+            id: Uuid::new_v4().to_string(),
+            title,
+            description: None,
+            notes: None,
+            is_completed: false,
+            created_at,
+            created_timezone: String::from(created_timezone.name()),
+            completed_at: None,
+            completed_timezone: None,
+            due_date: None,
+            due_time: None,
+            start_date: None,
+            recurrence: None,
+            recurrence_mode: None,
+            series_id: None,
+            occurrence: None,
+            parent_id: None,
+            completes_with_children: false,
+            project_id: None,
+            position: String::new(),
+            priority: Priority::None,
+            deleted_at: None,
+        };
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItemTagRecord {
+    pub todo_item_id: String,
+    pub tag_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartListRecord {
+    pub id: String,
+    pub name: String,
+    pub filter: String,
+    pub sort_order: i64,
+}
+
+// Lists that are missing count as empty, so that documents from before a
+// kind of thing existed can still be imported.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub projects: Vec<ProjectRecord>,
+    #[serde(default)]
+    pub tags: Vec<TagRecord>,
+    #[serde(default)]
+    pub todo_items: Vec<TodoItemRecord>,
+    #[serde(default)]
+    pub todo_item_tags: Vec<TodoItemTagRecord>,
+    #[serde(default)]
+    pub smart_lists: Vec<SmartListRecord>,
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+fn read_projects(connection: &mut Connection) -> Result<Vec<ProjectRecord>, AppError> {
+    let mut projects = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          name,
+          color,
+          icon,
+          isArchived,
+          sortOrder
+        from projects
+        order by sortOrder, id
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        projects.push(ProjectRecord {
+            id: statement.read::<String>(0)?,
+            name: statement.read::<String>(1)?,
+            color: statement.read::<String>(2)?,
+            icon: statement.read::<Option<String>>(3)?,
+            is_archived: statement.read::<String>(4)? == "true",
+            sort_order: statement.read::<i64>(5)?,
+        });
+    }
+
+    return Ok(projects);
+}
+
+fn read_tags(connection: &mut Connection) -> Result<Vec<TagRecord>, AppError> {
+    let mut tags = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          name,
+          color,
+          deletedAt
+        from tags
+        order by name collate nocase, id
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        tags.push(TagRecord {
+            id: statement.read::<String>(0)?,
+            name: statement.read::<String>(1)?,
+            color: statement.read::<String>(2)?,
+            deleted_at: statement
+                .read::<Option<String>>(3)?
+                .map(|deleted_at| timestamp::from_sql(&deleted_at))
+                .transpose()?,
+        });
+    }
+
+    return Ok(tags);
+}
+
+fn read_todo_items(connection: &mut Connection) -> Result<Vec<TodoItemRecord>, AppError> {
+    let mut todo_items = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          title,
+          description,
+          notes,
+          isCompleted,
+          createdAt,
+          createdTimezone,
+          completedAt,
+          completedTimezone,
+          dueDate,
+          dueTime,
+          startDate,
+          recurrence,
+          recurrenceMode,
+          seriesId,
+          occurrence,
+          parentId,
+          completesWithChildren,
+          projectId,
+          position,
+          priority,
+          deletedAt
+        from todoItems
+        order by position, id
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        todo_items.push(TodoItemRecord {
+            id: statement.read::<String>(0)?,
+            title: statement.read::<String>(1)?,
+            description: statement.read::<Option<String>>(2)?,
+            notes: statement.read::<Option<String>>(3)?,
+            is_completed: statement.read::<String>(4)? == "true",
+            created_at: timestamp::from_sql(&statement.read::<String>(5)?)?,
+            created_timezone: statement.read::<String>(6)?,
+            completed_at: statement
+                .read::<Option<String>>(7)?
+                .map(|completed_at| timestamp::from_sql(&completed_at))
+                .transpose()?,
+            completed_timezone: statement.read::<Option<String>>(8)?,
+            due_date: statement
+                .read::<Option<String>>(9)?
+                .map(|due_date| timestamp::date_from_sql(&due_date))
+                .transpose()?,
+            due_time: statement
+                .read::<Option<String>>(10)?
+                .map(|due_time| timestamp::time_from_sql(&due_time))
+                .transpose()?,
+            start_date: statement
+                .read::<Option<String>>(11)?
+                .map(|start_date| timestamp::date_from_sql(&start_date))
+                .transpose()?,
+            recurrence: statement.read::<Option<String>>(12)?,
+            recurrence_mode: statement
+                .read::<Option<String>>(13)?
+                .map(|recurrence_mode| RecurrenceMode::from_sql(&recurrence_mode))
+                .transpose()?,
+            series_id: statement.read::<Option<String>>(14)?,
+            occurrence: statement
+                .read::<Option<i64>>(15)?
+                .map(|occurrence| occurrence as u32),
+            parent_id: statement.read::<Option<String>>(16)?,
+            completes_with_children: statement.read::<String>(17)? == "true",
+            project_id: statement.read::<Option<String>>(18)?,
+            position: statement.read::<String>(19)?,
+            priority: Priority::from_sql(statement.read::<i64>(20)?)?,
+            deleted_at: statement
+                .read::<Option<String>>(21)?
+                .map(|deleted_at| timestamp::from_sql(&deleted_at))
+                .transpose()?,
+        });
+    }
+
+    return Ok(todo_items);
+}
+
+fn read_todo_item_tags(connection: &mut Connection) -> Result<Vec<TodoItemTagRecord>, AppError> {
+    let mut todo_item_tags = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          todoItemId,
+          tagId
+        from todoItemsTags
+        order by todoItemId, tagId
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        todo_item_tags.push(TodoItemTagRecord {
+            todo_item_id: statement.read::<String>(0)?,
+            tag_id: statement.read::<String>(1)?,
+        });
+    }
+
+    return Ok(todo_item_tags);
+}
+
+fn read_smart_lists(connection: &mut Connection) -> Result<Vec<SmartListRecord>, AppError> {
+    return Ok(smart_list::get_all(connection)?
+        .into_iter()
+        .map(|smart_list| SmartListRecord {
+            id: smart_list.id,
+            name: smart_list.name,
+            filter: smart_list.filter,
+            sort_order: smart_list.sort_order,
+        })
+        .collect());
+}
+
+fn read_settings(connection: &mut Connection) -> Result<BTreeMap<String, String>, AppError> {
+    let mut settings = BTreeMap::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          key,
+          value
+        from settings
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        settings.insert(statement.read::<String>(0)?, statement.read::<String>(1)?);
+    }
+
+    return Ok(settings);
+}
+
+/// Everything in the database, trash included.
+pub fn read(connection: &mut Connection, exported_at: DateTime<Utc>) -> Result<Document, AppError> {
+    return Ok(Document {
+        version: VERSION,
+        exported_at,
+        projects: read_projects(connection)?,
+        tags: read_tags(connection)?,
+        todo_items: read_todo_items(connection)?,
+        todo_item_tags: read_todo_item_tags(connection)?,
+        smart_lists: read_smart_lists(connection)?,
+        settings: read_settings(connection)?,
+    });
+}
+
+/// Writes everything in the database, trash included, to the file at `path`.
+pub fn export(
+    connection: &mut Connection,
+    path: &str,
+    exported_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let document = read(connection, exported_at)?;
+
+    std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+
+    Ok(())
+}
+
+// Catches what the database wouldn't, or would only with a less helpful
+// message.
+fn validate(document: &Document) -> Result<(), AppError> {
+    if document.version == 0 || document.version > VERSION {
+        return Err(AppError::Validation(format!(
+            "Version {} of the export format is not supported, only versions up to {} are",
+            document.version, VERSION
+        )));
+    }
+
+    for todo_item in &document.todo_items {
+        timestamp::parse_timezone(&todo_item.created_timezone)?;
+
+        if let Some(completed_timezone) = &todo_item.completed_timezone {
+            timestamp::parse_timezone(completed_timezone)?;
+        }
+
+        if let Some(recurrence) = &todo_item.recurrence {
+            Rule::parse(recurrence)?;
+        }
+
+        rank::validate(&todo_item.position)?;
+    }
+
+    for smart_list in &document.smart_lists {
+        smart_list::validate(&smart_list.filter)?;
+    }
+
+    Ok(())
+}
+
+fn new_ids<'a>(ids: impl Iterator<Item = &'a String>) -> HashMap<String, String> {
+    return ids
+        .map(|id| (id.clone(), Uuid::new_v4().to_string()))
+        .collect();
+}
+
+// Ids that aren't in the document are left alone, so a copy can still refer
+// to what's already in the database.
+fn remap(ids: &HashMap<String, String>, id: String) -> String {
+    return ids.get(&id).cloned().unwrap_or(id);
+}
+
+// Gives everything in the document a new id and moves its items after the
+// ones already in the database, keeping their order. Settings aren't copied,
+// since there's only one of each.
+fn duplicate(connection: &mut Connection, document: Document) -> Result<Document, AppError> {
+    let project_ids = new_ids(document.projects.iter().map(|project| &project.id));
+    let tag_ids = new_ids(document.tags.iter().map(|tag| &tag.id));
+    let todo_item_ids = new_ids(document.todo_items.iter().map(|todo_item| &todo_item.id));
+    // A series is named after its first occurrence, so it follows that
+    // item's new id, and only gets one of its own when that item isn't in the
+    // document.
+    let mut series_ids = todo_item_ids.clone();
+    series_ids.extend(new_ids(
+        document
+            .todo_items
+            .iter()
+            .filter_map(|todo_item| todo_item.series_id.as_ref())
+            .filter(|series_id| !todo_item_ids.contains_key(*series_id)),
+    ));
+
+    let mut todo_items = document.todo_items;
+    todo_items.sort_by(|a, b| a.position.cmp(&b.position));
+
+    let mut last_position = todo_item::get_last_position(connection)?;
+
+    let todo_items = todo_items
+        .into_iter()
+        .map(|todo_item| -> Result<TodoItemRecord, AppError> {
+            let position = rank::between(last_position.as_deref(), None)?;
+            last_position = Some(position.clone());
+
+            return Ok(TodoItemRecord {
+                id: remap(&todo_item_ids, todo_item.id),
+                series_id: todo_item.series_id.map(|id| remap(&series_ids, id)),
+                parent_id: todo_item.parent_id.map(|id| remap(&todo_item_ids, id)),
+                project_id: todo_item.project_id.map(|id| remap(&project_ids, id)),
+                position,
+                ..todo_item
+            });
+        })
+        .collect::<Result<Vec<TodoItemRecord>, AppError>>()?;
+
+    return Ok(Document {
+        version: document.version,
+        exported_at: document.exported_at,
+        projects: document
+            .projects
+            .into_iter()
+            .map(|project| ProjectRecord {
+                id: remap(&project_ids, project.id),
+                ..project
+            })
+            .collect(),
+        tags: document
+            .tags
+            .into_iter()
+            .map(|tag| TagRecord {
+                id: remap(&tag_ids, tag.id),
+                ..tag
+            })
+            .collect(),
+        todo_items,
+        todo_item_tags: document
+            .todo_item_tags
+            .into_iter()
+            .map(|todo_item_tag| TodoItemTagRecord {
+                todo_item_id: remap(&todo_item_ids, todo_item_tag.todo_item_id),
+                tag_id: remap(&tag_ids, todo_item_tag.tag_id),
+            })
+            .collect(),
+        smart_lists: document
+            .smart_lists
+            .into_iter()
+            .map(|smart_list| SmartListRecord {
+                id: Uuid::new_v4().to_string(),
+                ..smart_list
+            })
+            .collect(),
+        settings: BTreeMap::new(),
+    });
+}
+
+fn delete_all(connection: &mut Connection) -> Result<(), AppError> {
+    for sql in [
+        "delete from todoItemsTags",
+        "delete from todoItems",
+        "delete from tags",
+        "delete from projects",
+        "delete from smartLists",
+    ] {
+        connection.prepare(sql)?.next()?;
+    }
+
+    Ok(())
+}
+
+// Which tags an item has is part of the item, so merging it replaces them
+// rather than adding to them.
+fn delete_todo_item_tags(connection: &mut Connection, document: &Document) -> Result<(), AppError> {
+    for todo_item in &document.todo_items {
+        let statement = connection.prepare("delete from todoItemsTags where todoItemId = ?")?;
+
+        statement.bind(1, &*todo_item.id)?;
+
+        statement.next()?;
+    }
+
+    Ok(())
+}
+
+fn write_project(connection: &mut Connection, project: &ProjectRecord) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        insert into projects (
+          id,
+          name,
+          color,
+          icon,
+          isArchived,
+          sortOrder
+        ) values (
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?
+        )
+        on conflict (id) do update set
+          name = excluded.name,
+          color = excluded.color,
+          icon = excluded.icon,
+          isArchived = excluded.isArchived,
+          sortOrder = excluded.sortOrder
+      ",
+    )?;
+
+    statement.bind(1, &*project.id)?;
+    statement.bind(2, &*project.name)?;
+    statement.bind(3, &*project.color)?;
+    statement.bind(4, project.icon.as_deref())?;
+    statement.bind(5, project.is_archived.to_string().as_str())?;
+    statement.bind(6, project.sort_order)?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn write_tag(connection: &mut Connection, tag: &TagRecord) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        insert into tags (
+          id,
+          name,
+          color,
+          deletedAt
+        ) values (
+          ?,
+          ?,
+          ?,
+          ?
+        )
+        on conflict (id) do update set
+          name = excluded.name,
+          color = excluded.color,
+          deletedAt = excluded.deletedAt
+      ",
+    )?;
+
+    statement.bind(1, &*tag.id)?;
+    statement.bind(2, &*tag.name)?;
+    statement.bind(3, &*tag.color)?;
+    statement.bind(
+        4,
+        tag.deleted_at
+            .map(|deleted_at| timestamp::to_sql(&deleted_at))
+            .as_deref(),
+    )?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn write_todo_item(
+    connection: &mut Connection,
+    todo_item: &TodoItemRecord,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        insert into todoItems (
+          id,
+          title,
+          description,
+          notes,
+          isCompleted,
+          createdAt,
+          createdTimezone,
+          completedAt,
+          completedTimezone,
+          dueDate,
+          dueTime,
+          startDate,
+          recurrence,
+          recurrenceMode,
+          seriesId,
+          occurrence,
+          parentId,
+          completesWithChildren,
+          projectId,
+          position,
+          priority,
+          deletedAt
+        ) values (
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?
+        )
+        on conflict (id) do update set
+          title = excluded.title,
+          description = excluded.description,
+          notes = excluded.notes,
+          isCompleted = excluded.isCompleted,
+          createdAt = excluded.createdAt,
+          createdTimezone = excluded.createdTimezone,
+          completedAt = excluded.completedAt,
+          completedTimezone = excluded.completedTimezone,
+          dueDate = excluded.dueDate,
+          dueTime = excluded.dueTime,
+          startDate = excluded.startDate,
+          recurrence = excluded.recurrence,
+          recurrenceMode = excluded.recurrenceMode,
+          seriesId = excluded.seriesId,
+          occurrence = excluded.occurrence,
+          parentId = excluded.parentId,
+          completesWithChildren = excluded.completesWithChildren,
+          projectId = excluded.projectId,
+          position = excluded.position,
+          priority = excluded.priority,
+          deletedAt = excluded.deletedAt
+      ",
+    )?;
+
+    statement.bind(1, &*todo_item.id)?;
+    statement.bind(2, &*todo_item.title)?;
+    statement.bind(3, todo_item.description.as_deref())?;
+    statement.bind(4, todo_item.notes.as_deref())?;
+    statement.bind(5, todo_item.is_completed.to_string().as_str())?;
+    statement.bind(6, &*timestamp::to_sql(&todo_item.created_at))?;
+    statement.bind(7, &*todo_item.created_timezone)?;
+    statement.bind(
+        8,
+        todo_item
+            .completed_at
+            .map(|completed_at| timestamp::to_sql(&completed_at))
+            .as_deref(),
+    )?;
+    statement.bind(9, todo_item.completed_timezone.as_deref())?;
+    statement.bind(
+        10,
+        todo_item
+            .due_date
+            .map(|due_date| timestamp::date_to_sql(&due_date))
+            .as_deref(),
+    )?;
+    statement.bind(
+        11,
+        todo_item
+            .due_time
+            .map(|due_time| timestamp::time_to_sql(&due_time))
+            .as_deref(),
+    )?;
+    statement.bind(
+        12,
+        todo_item
+            .start_date
+            .map(|start_date| timestamp::date_to_sql(&start_date))
+            .as_deref(),
+    )?;
+    statement.bind(13, todo_item.recurrence.as_deref())?;
+    statement.bind(
+        14,
+        todo_item
+            .recurrence_mode
+            .map(|recurrence_mode| recurrence_mode.to_sql()),
+    )?;
+    statement.bind(15, todo_item.series_id.as_deref())?;
+    statement.bind(16, todo_item.occurrence.map(i64::from))?;
+    statement.bind(17, todo_item.parent_id.as_deref())?;
+    statement.bind(18, todo_item.completes_with_children.to_string().as_str())?;
+    statement.bind(19, todo_item.project_id.as_deref())?;
+    statement.bind(20, &*todo_item.position)?;
+    statement.bind(21, todo_item.priority.to_sql())?;
+    statement.bind(
+        22,
+        todo_item
+            .deleted_at
+            .map(|deleted_at| timestamp::to_sql(&deleted_at))
+            .as_deref(),
+    )?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn write_todo_item_tag(
+    connection: &mut Connection,
+    todo_item_tag: &TodoItemTagRecord,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        insert into todoItemsTags (
+          id,
+          todoItemId,
+          tagId
+        ) values (
+          ?,
+          ?,
+          ?
+        )
+        on conflict (todoItemId, tagId) do nothing
+      ",
+    )?;
+
+    statement.bind(1, &*Uuid::new_v4().to_string())?;
+    statement.bind(2, &*todo_item_tag.todo_item_id)?;
+    statement.bind(3, &*todo_item_tag.tag_id)?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn write_smart_list(
+    connection: &mut Connection,
+    smart_list: &SmartListRecord,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        insert into smartLists (
+          id,
+          name,
+          filter,
+          sortOrder
+        ) values (
+          ?,
+          ?,
+          ?,
+          ?
+        )
+        on conflict (id) do update set
+          name = excluded.name,
+          filter = excluded.filter,
+          sortOrder = excluded.sortOrder
+      ",
+    )?;
+
+    statement.bind(1, &*smart_list.id)?;
+    statement.bind(2, &*smart_list.name)?;
+    statement.bind(3, &*smart_list.filter)?;
+    statement.bind(4, smart_list.sort_order)?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn write_setting(connection: &mut Connection, key: &str, value: &str) -> Result<(), AppError> {
+    match key {
+        setting::TRASH_RETENTION_DAYS => {
+            let days = value
+                .parse::<u32>()
+                .map_err(|_| AppError::Validation(format!("{} is not a number of days", value)))?;
+
+            trash::set_retention_days(connection, days)
+        }
+        setting::ICAL_FEED_ENABLED => match value {
+            "true" => ical::set_feed_enabled(connection, true),
+            "false" => ical::set_feed_enabled(connection, false),
+            _ => Err(AppError::Validation(format!(
+                "{} is not true or false",
+                value
+            ))),
+        },
+        _ => Err(AppError::Validation(format!("Unknown setting {}", key))),
+    }
+}
+
+// Foreign keys are only checked once everything is written, so that items can
+// come before their parents in the document.
+fn check_references(connection: &mut Connection) -> Result<(), AppError> {
+    let statement = connection.prepare("pragma foreign_key_check")?;
+
+    if let State::Row = statement.next()? {
+        return Err(AppError::Validation(format!(
+            "Imported {} refer to {} that don't exist",
+            statement.read::<String>(0)?,
+            statement.read::<String>(2)?
+        )));
+    }
+
+    Ok(())
+}
+
+// Every parent exists, but that doesn't stop two items from being each
+// other's parent.
+fn check_parents(connection: &mut Connection) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        with recursive ancestors (id, ancestorId, depth) as (
+          select
+            id,
+            parentId,
+            1
+          from todoItems
+          where parentId is not null
+          union all
+          select
+            ancestors.id,
+            todoItems.parentId,
+            ancestors.depth + 1
+          from ancestors
+          join todoItems on todoItems.id = ancestors.ancestorId
+          where
+            todoItems.parentId is not null
+            and ancestors.depth < (select count(*) from todoItems)
+        )
+        select id
+        from ancestors
+        where ancestorId = id
+        limit 1
+      ",
+    )?;
+
+    if let State::Row = statement.next()? {
+        return Err(AppError::Validation(format!(
+            "Todo item {} is one of its own subtasks",
+            statement.read::<String>(0)?
+        )));
+    }
+
+    Ok(())
+}
+
+fn write(connection: &mut Connection, document: &Document) -> Result<ImportSummary, AppError> {
+    connection.execute("pragma defer_foreign_keys = on")?;
+
+    for project in &document.projects {
+        write_project(connection, project)?;
+    }
+
+    for tag in &document.tags {
+        write_tag(connection, tag)?;
+    }
+
+    for todo_item in &document.todo_items {
+        write_todo_item(connection, todo_item)?;
+    }
+
+    for todo_item_tag in &document.todo_item_tags {
+        write_todo_item_tag(connection, todo_item_tag)?;
+    }
+
+    for smart_list in &document.smart_lists {
+        write_smart_list(connection, smart_list)?;
+    }
+
+    for (key, value) in &document.settings {
+        write_setting(connection, key, value)?;
+    }
+
+    check_references(connection)?;
+    check_parents(connection)?;
+
+    return Ok(ImportSummary {
+        todo_items: document.todo_items.len() as u32,
+        tags: document.tags.len() as u32,
+        projects: document.projects.len() as u32,
+        smart_lists: document.smart_lists.len() as u32,
+    });
+}
+
+/// Writes `document` to the database. Nothing is changed unless all of it
+/// can be written. Imports can't be undone, and the steps before them can't be
+/// either, since they might refer to things the import replaced.
+pub fn import_document(
+    connection: &mut Connection,
+    document: Document,
+    mode: ImportMode,
+) -> Result<ImportSummary, AppError> {
+    validate(&document)?;
+
+    let summary = connection.savepoint(|connection| {
+        let summary = match mode {
+            ImportMode::Replace => {
+                delete_all(connection)?;
+                write(connection, &document)?
+            }
+            ImportMode::MergeById => {
+                delete_todo_item_tags(connection, &document)?;
+                write(connection, &document)?
+            }
+            ImportMode::DuplicateAsNew => {
+                let document = duplicate(connection, document)?;
+                write(connection, &document)?
+            }
+        };
+
+        journal::clear(connection)?;
+
+        return Ok(summary);
+    })?;
+
+    connection.notify_written();
+
+    return Ok(summary);
+}
+
+/// Reads a document written by `export` from the file at `path` into the
+/// database.
+pub fn import(
+    connection: &mut Connection,
+    path: &str,
+    mode: ImportMode,
+) -> Result<ImportSummary, AppError> {
+    let document: Document = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+    return import_document(connection, document, mode);
+}
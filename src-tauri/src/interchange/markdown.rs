@@ -0,0 +1,633 @@
+//! Markdown reports of the items done or due over a range of days, for
+//! pasting into standups and weekly reports:
+//!
+//! ```text
+//! ## Monday, March 2, 2026
+//!
+//! - [x] Call the bank #work
+//!   Asked about the transfer fee.
+//!   - [ ] Send the forms
+//! ```
+//!
+//! Completed items count on the day they were completed, and the rest on the
+//! day they're due. Reports are rendered with a Tera template
+//! (https://keats.github.io/tera/docs/), and users who want them to look
+//! different can pass their own, starting from `DEFAULT_TEMPLATE`.
+//!
+//! Checklists drafted in other tools can be imported the other way around.
+//! Each heading becomes a project for the items under it, each bullet an
+//! item, nested under the bullet it's indented beneath, and each `#hashtag`
+//! in a bullet one of the item's tags. Bullets checked with `[x]` are
+//! completed, unless a bullet nested under them isn't, and indented lines that
+//! aren't bullets are the notes of the bullet above them.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::interchange::json::{self, ImportMode, TodoItemRecord};
+use crate::interchange::{ImportSummary, NewItems};
+use crate::timestamp;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tera::{Context, Tera};
+
+/// Renders a heading for each group and a checkbox for each item, with its
+/// subtasks and notes indented beneath it and its tags after its title.
+pub const DEFAULT_TEMPLATE: &str = r#"# {{ start_date }}{% if end_date != start_date %} to {{ end_date }}{% endif %}
+{% for group in groups %}
+## {{ group.name }}
+
+{% for item in group.items -%}
+{% for _ in range(end=item.depth) %}  {% endfor %}- [{% if item.is_completed %}x{% else %} {% endif %}] {{ item.title }}{% for tag in item.tags %} #{{ tag | replace(from=" ", to="_") }}{% endfor %}
+{% if item.notes %}{% for line in item.notes | split(pat="
+") %}{% for _ in range(end=item.depth + 1) %}  {% endfor %}{{ line }}
+{% endfor %}{% endif %}
+{%- endfor %}
+{%- endfor %}
+"#;
+
+const NO_PROJECT: &str = "No project";
+const NO_TAGS: &str = "No tags";
+
+/// What the items in a report are grouped under.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Grouping {
+    Day,
+    Project,
+    /// Items with more than one tag are under each of them.
+    Tag,
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownOptions {
+    /// The first and last days of the report, both included.
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub grouping: Grouping,
+    /// The timezone that decides which day items were completed on.
+    pub timezone: String,
+    /// A Tera template to render instead of `DEFAULT_TEMPLATE`.
+    pub template: Option<String>,
+}
+
+// What templates can use for each item. Subtasks come right after their
+// parent, one level deeper, when they're in the same group.
+#[derive(Serialize, Debug)]
+struct ItemContext {
+    id: String,
+    title: String,
+    description: Option<String>,
+    notes: Option<String>,
+    is_completed: bool,
+    completed_on: Option<NaiveDate>,
+    due_date: Option<NaiveDate>,
+    due_time: Option<NaiveTime>,
+    project: Option<String>,
+    tags: Vec<String>,
+    depth: usize,
+}
+
+#[derive(Serialize, Debug)]
+struct GroupContext {
+    name: String,
+    items: Vec<ItemContext>,
+}
+
+#[derive(Serialize, Debug)]
+struct ReportContext {
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    grouping: Grouping,
+    groups: Vec<GroupContext>,
+}
+
+// The day an item counts on, if it's in the report at all.
+fn report_day(todo_item: &TodoItemRecord, timezone: Tz) -> Option<NaiveDate> {
+    if todo_item.is_completed {
+        return todo_item
+            .completed_at
+            .map(|completed_at| completed_at.with_timezone(&timezone).date_naive());
+    }
+
+    return todo_item.due_date;
+}
+
+// Orders a group's items so subtasks follow their parents.
+fn nest(
+    todo_items: &[&TodoItemRecord],
+    make_item: &dyn Fn(&TodoItemRecord, usize) -> ItemContext,
+) -> Vec<ItemContext> {
+    let ids: HashSet<&str> = todo_items
+        .iter()
+        .map(|todo_item| todo_item.id.as_str())
+        .collect();
+
+    let mut children: HashMap<&str, Vec<&TodoItemRecord>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for todo_item in todo_items {
+        match todo_item.parent_id.as_deref() {
+            Some(parent_id) if ids.contains(parent_id) => {
+                children.entry(parent_id).or_default().push(todo_item)
+            }
+            _ => roots.push(*todo_item),
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut stack: Vec<(&TodoItemRecord, usize)> = roots
+        .into_iter()
+        .rev()
+        .map(|todo_item| (todo_item, 0))
+        .collect();
+
+    while let Some((todo_item, depth)) = stack.pop() {
+        items.push(make_item(todo_item, depth));
+
+        if let Some(children) = children.get(todo_item.id.as_str()) {
+            stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+        }
+    }
+
+    return items;
+}
+
+fn build_context(
+    connection: &mut Connection,
+    options: &MarkdownOptions,
+    now: DateTime<Utc>,
+) -> Result<ReportContext, AppError> {
+    if options.end_date < options.start_date {
+        return Err(AppError::Validation(String::from(
+            "The report can't end before it starts",
+        )));
+    }
+
+    let timezone = timestamp::parse_timezone(&options.timezone)?;
+    let document = json::read(connection, now)?;
+
+    let projects: HashMap<&str, (&str, &str, i64)> = document
+        .projects
+        .iter()
+        .map(|project| {
+            (
+                project.id.as_str(),
+                (
+                    project.id.as_str(),
+                    project.name.as_str(),
+                    project.sort_order,
+                ),
+            )
+        })
+        .collect();
+    let tags: HashMap<&str, &str> = document
+        .tags
+        .iter()
+        .filter(|tag| tag.deleted_at.is_none())
+        .map(|tag| (tag.id.as_str(), tag.name.as_str()))
+        .collect();
+
+    let mut item_tags: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for todo_item_tag in &document.todo_item_tags {
+        if let Some(tag) = tags.get(todo_item_tag.tag_id.as_str()).copied() {
+            item_tags
+                .entry(todo_item_tag.todo_item_id.as_str())
+                .or_default()
+                .push(tag);
+        }
+    }
+
+    for tag_names in item_tags.values_mut() {
+        tag_names.sort_by_key(|tag_name| tag_name.to_lowercase());
+    }
+
+    let project_of = |todo_item: &TodoItemRecord| {
+        todo_item
+            .project_id
+            .as_deref()
+            .and_then(|project_id| projects.get(project_id).copied())
+    };
+
+    // Groups are keyed so they sort in the order they're shown: days in
+    // order, projects in their order, tags by name, and items without a
+    // project or tag last.
+    let mut groups: BTreeMap<(u8, i64, String), (String, Vec<&TodoItemRecord>)> = BTreeMap::new();
+
+    for todo_item in &document.todo_items {
+        if todo_item.deleted_at.is_some() {
+            continue;
+        }
+
+        let day = match report_day(todo_item, timezone) {
+            Some(day) if day >= options.start_date && day <= options.end_date => day,
+            _ => continue,
+        };
+
+        let keys = match options.grouping {
+            Grouping::Day => vec![(
+                (0, 0, timestamp::date_to_sql(&day)),
+                day.format("%A, %B %-d, %Y").to_string(),
+            )],
+            Grouping::Project => vec![match project_of(todo_item) {
+                Some((id, name, sort_order)) => {
+                    ((0, sort_order, String::from(id)), String::from(name))
+                }
+                None => ((1, 0, String::new()), String::from(NO_PROJECT)),
+            }],
+            Grouping::Tag => match item_tags.get(todo_item.id.as_str()) {
+                Some(tag_names) => tag_names
+                    .iter()
+                    .map(|tag_name| ((0, 0, tag_name.to_lowercase()), String::from(*tag_name)))
+                    .collect(),
+                None => vec![((1, 0, String::new()), String::from(NO_TAGS))],
+            },
+        };
+
+        for (key, name) in keys {
+            groups
+                .entry(key)
+                .or_insert_with(|| (name, Vec::new()))
+                .1
+                .push(todo_item);
+        }
+    }
+
+    let make_item = |todo_item: &TodoItemRecord, depth: usize| ItemContext {
+        id: todo_item.id.clone(),
+        title: todo_item.title.clone(),
+        description: todo_item.description.clone(),
+        notes: todo_item.notes.clone(),
+        is_completed: todo_item.is_completed,
+        completed_on: report_day(todo_item, timezone).filter(|_| todo_item.is_completed),
+        due_date: todo_item.due_date,
+        due_time: todo_item.due_time,
+        project: project_of(todo_item).map(|(_, name, _)| String::from(name)),
+        tags: item_tags
+            .get(todo_item.id.as_str())
+            .map(|tag_names| {
+                tag_names
+                    .iter()
+                    .map(|tag_name| String::from(*tag_name))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        depth,
+    };
+
+    return Ok(ReportContext {
+        start_date: options.start_date,
+        end_date: options.end_date,
+        grouping: options.grouping,
+        groups: groups
+            .into_values()
+            .map(|(name, todo_items)| GroupContext {
+                name,
+                items: nest(&todo_items, &make_item),
+            })
+            .collect(),
+    });
+}
+
+/// Renders the report `options` describe.
+pub fn render(
+    connection: &mut Connection,
+    options: &MarkdownOptions,
+    now: DateTime<Utc>,
+) -> Result<String, AppError> {
+    let context = Context::from_serialize(build_context(connection, options, now)?)?;
+    let template = options.template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+
+    return Ok(Tera::one_off(template, &context, false)?);
+}
+
+/// Renders the report `options` describe to the file at `path`.
+pub fn export(
+    connection: &mut Connection,
+    path: &str,
+    options: &MarkdownOptions,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    std::fs::write(path, render(connection, options, now)?)?;
+
+    Ok(())
+}
+
+/// How far a tab indents a line.
+const TAB_WIDTH: usize = 4;
+
+struct Bullet {
+    todo_item: TodoItemRecord,
+    project: Option<String>,
+    tag_names: Vec<String>,
+    indent: usize,
+    // The index of the bullet it's nested under.
+    parent: Option<usize>,
+}
+
+fn measure_indent(line: &str) -> (usize, &str) {
+    let mut indent = 0;
+
+    for (index, character) in line.char_indices() {
+        match character {
+            ' ' => indent += 1,
+            '\t' => indent += TAB_WIDTH - indent % TAB_WIDTH,
+            _ => return (indent, &line[index..]),
+        }
+    }
+
+    return (indent, "");
+}
+
+fn parse_heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+
+    if level == 0 || level > 6 || !(text.is_empty() || text.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    // Headings can be closed with as many #s as they like.
+    return Some(text.trim().trim_end_matches('#').trim_end());
+}
+
+// Returns whether the bullet is checked, or none if it has no checkbox, and
+// its text.
+fn parse_bullet(line: &str) -> Option<(Option<bool>, &str)> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker_length = match line[digits..].chars().next() {
+        Some('-') | Some('*') | Some('+') if digits == 0 => 1,
+        Some('.') | Some(')') if digits > 0 => digits + 1,
+        _ => return None,
+    };
+    let rest = &line[marker_length..];
+
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let rest = rest.trim_start();
+
+    for (checkbox, is_checked) in [("[ ]", false), ("[x]", true), ("[X]", true)] {
+        if let Some(text) = rest.strip_prefix(checkbox) {
+            if text.is_empty() || text.starts_with([' ', '\t']) {
+                return Some((Some(is_checked), text.trim()));
+            }
+        }
+    }
+
+    return Some((None, rest.trim()));
+}
+
+// A hashtag is a word starting with `#`, up to the first character that can't
+// be part of a tag name, such as the comma after it.
+fn split_hashtags(text: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tag_names = Vec::new();
+
+    for word in text.split_whitespace() {
+        let name: String = word
+            .strip_prefix('#')
+            .unwrap_or("")
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || ['_', '-', '/'].contains(c))
+            .collect();
+
+        if name.is_empty() {
+            words.push(word);
+        } else {
+            tag_names.push(name);
+        }
+    }
+
+    return (words.join(" "), tag_names);
+}
+
+// Reads the bullets in a Markdown checklist, parents before their subtasks.
+fn parse(text: &str, timezone: Tz, now: DateTime<Utc>) -> Vec<Bullet> {
+    let mut bullets: Vec<Bullet> = Vec::new();
+    // The bullets each line could be nested under, innermost last.
+    let mut parents: Vec<usize> = Vec::new();
+    let mut project: Option<String> = None;
+    let mut is_in_code_block = false;
+
+    for line in text.lines() {
+        let (indent, content) = measure_indent(line);
+
+        if content.starts_with("```") || content.starts_with("~~~") {
+            is_in_code_block = !is_in_code_block;
+            continue;
+        }
+
+        if is_in_code_block || content.is_empty() {
+            continue;
+        }
+
+        while let Some(parent) = parents.last() {
+            if bullets[*parent].indent < indent {
+                break;
+            }
+
+            parents.pop();
+        }
+
+        if let Some(heading) = parse_heading(content) {
+            parents.clear();
+            project = Some(String::from(heading)).filter(|heading| !heading.is_empty());
+            continue;
+        }
+
+        let (is_checked, bullet_text) = match parse_bullet(content) {
+            Some(bullet) => bullet,
+            None => {
+                // Lines that aren't indented beneath a bullet are ordinary
+                // paragraphs, which aren't part of any item.
+                if let Some(parent) = parents.last() {
+                    let todo_item = &mut bullets[*parent].todo_item;
+                    let notes = todo_item.notes.get_or_insert_with(String::new);
+
+                    if !notes.is_empty() {
+                        notes.push('\n');
+                    }
+
+                    notes.push_str(content.trim_end());
+                }
+
+                continue;
+            }
+        };
+
+        let (title, tag_names) = split_hashtags(bullet_text);
+
+        if title.is_empty() {
+            continue;
+        }
+
+        let parent = parents.last().copied();
+        let mut todo_item = TodoItemRecord::new(title, now, timezone);
+
+        todo_item.parent_id = parent.map(|parent| bullets[parent].todo_item.id.clone());
+
+        if is_checked == Some(true) {
+            todo_item.is_completed = true;
+            todo_item.completed_at = Some(now);
+            todo_item.completed_timezone = Some(String::from(timezone.name()));
+        }
+
+        parents.push(bullets.len());
+        bullets.push(Bullet {
+            todo_item,
+            project: project.clone(),
+            tag_names,
+            indent,
+            parent,
+        });
+    }
+
+    // A parent is only done once its subtasks are, so a checked bullet with
+    // unchecked ones under it is reopened. Subtasks come after their parents,
+    // so going backwards reopens whole chains of them.
+    for index in (0..bullets.len()).rev() {
+        if let Some(parent) = bullets[index]
+            .parent
+            .filter(|_| !bullets[index].todo_item.is_completed)
+        {
+            let todo_item = &mut bullets[parent].todo_item;
+
+            todo_item.is_completed = false;
+            todo_item.completed_at = None;
+            todo_item.completed_timezone = None;
+        }
+    }
+
+    return bullets;
+}
+
+/// Adds the bullets in the Markdown file at `path` after the items already in
+/// the database, creating the projects and tags they mention that don't
+/// exist yet.
+pub fn import(
+    connection: &mut Connection,
+    path: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<ImportSummary, AppError> {
+    let text = std::fs::read_to_string(path)?;
+    let mut items = NewItems::new(connection, now)?;
+
+    for bullet in parse(&text, timezone, now) {
+        let mut todo_item = bullet.todo_item;
+
+        todo_item.project_id = bullet.project.map(|project| items.project_id(&project));
+
+        let tag_ids = bullet
+            .tag_names
+            .iter()
+            .map(|tag_name| items.tag_id(tag_name))
+            .collect();
+
+        items.add(todo_item, tag_ids)?;
+    }
+
+    return items.import(connection, ImportMode::DuplicateAsNew);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_text(text: &str) -> Vec<Bullet> {
+        return parse(text, chrono_tz::UTC, Utc::now());
+    }
+
+    fn titles(bullets: &[Bullet]) -> Vec<&str> {
+        return bullets
+            .iter()
+            .map(|bullet| bullet.todo_item.title.as_str())
+            .collect();
+    }
+
+    #[test]
+    fn nests_bullets_by_indentation() {
+        let bullets = parse_text("- A\n  - B\n    - C\n  - D\n\t- E\n- F\n1. G\n   2) H\n");
+
+        assert_eq!(
+            titles(&bullets),
+            vec!["A", "B", "C", "D", "E", "F", "G", "H"]
+        );
+        assert_eq!(
+            bullets
+                .iter()
+                .map(|bullet| bullet.parent)
+                .collect::<Vec<_>>(),
+            vec![
+                None,
+                Some(0),
+                Some(1),
+                Some(0),
+                Some(3),
+                None,
+                None,
+                Some(6)
+            ]
+        );
+        assert_eq!(
+            bullets[1].todo_item.parent_id,
+            Some(bullets[0].todo_item.id.clone())
+        );
+    }
+
+    #[test]
+    fn extracts_hashtags() {
+        let bullets = parse_text("- Call the bank #work, #home/errands about #1 and C#\n- #only\n");
+
+        assert_eq!(titles(&bullets), vec!["Call the bank about and C#"]);
+        assert_eq!(bullets[0].tag_names, vec!["work", "home/errands", "1"]);
+    }
+
+    #[test]
+    fn puts_items_under_headings_in_projects() {
+        let bullets = parse_text("- A\n# Work\n- B\n  - C\n## Home ##\n- D\n#\n- E\n#hashtag\n");
+
+        assert_eq!(
+            bullets
+                .iter()
+                .map(|bullet| bullet.project.as_deref())
+                .collect::<Vec<_>>(),
+            vec![None, Some("Work"), Some("Work"), Some("Home"), None]
+        );
+        assert_eq!(bullets[4].parent, None);
+    }
+
+    #[test]
+    fn reads_indented_lines_as_notes() {
+        let bullets = parse_text(
+            "- A\n  First line\n  - B\n    About B\n  Second line\nNot a note\n```\n- Not an item\n```\n",
+        );
+
+        assert_eq!(titles(&bullets), vec!["A", "B"]);
+        assert_eq!(
+            bullets[0].todo_item.notes.as_deref(),
+            Some("First line\nSecond line")
+        );
+        assert_eq!(bullets[1].todo_item.notes.as_deref(), Some("About B"));
+    }
+
+    #[test]
+    fn reopens_checked_bullets_with_unchecked_ones_under_them() {
+        let bullets = parse_text("- [x] A\n  - [x] B\n    - [ ] C\n- [x] D\n  - [X] E\n- F\n");
+
+        assert_eq!(
+            bullets
+                .iter()
+                .map(|bullet| bullet.todo_item.is_completed)
+                .collect::<Vec<_>>(),
+            vec![false, false, false, true, true, false]
+        );
+        assert_eq!(bullets[0].todo_item.completed_at, None);
+        assert!(bullets[3].todo_item.completed_at.is_some());
+    }
+}
@@ -0,0 +1,651 @@
+//! Taskwarrior's JSON task format (https://taskwarrior.org/docs/design/task/),
+//! as written by `task export` and read by `task import`:
+//!
+//! ```text
+//! [{"uuid":"5f0c6a44-2d4b-4c1e-9a51-0f1fd2f8e3b7","description":"Call the bank",
+//!   "status":"pending","entry":"20240301T090000Z","due":"20240304T083000Z",
+//!   "project":"Errands","priority":"H","tags":["phone"]}]
+//! ```
+//!
+//! The uuid is the item's id, so syncing the same tasks back and forth updates
+//! them instead of adding copies. Tasks are pending, completed or deleted,
+//! which for items means in the trash. Annotations become notes, scheduled
+//! dates start dates, and a task depending on others is the parent of those
+//! subtasks. Taskwarrior has no urgent priority, so urgent items are `H` like
+//! high ones, and recurrences it can't express are left out. Both are kept
+//! when the tasks come back, so syncing doesn't lose them. Recurring
+//! templates are skipped on import, since their instances are the tasks.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::interchange::json::{self, ImportMode, TodoItemRecord};
+use crate::interchange::{ImportSummary, NewItems};
+use crate::models::todo_item::Priority;
+use crate::recurrence::{ByDay, Frequency, RecurrenceMode, Rule};
+use crate::timestamp;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Annotation {
+    entry: String,
+    description: String,
+}
+
+// Taskwarrior 2 writes dependencies as one comma separated string, and 3 as
+// a list.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum Depends {
+    List(Vec<String>),
+    Text(String),
+}
+
+impl Depends {
+    fn uuids(&self) -> Vec<&str> {
+        match self {
+            Depends::List(uuids) => uuids.iter().map(String::as_str).collect(),
+            Depends::Text(uuids) => uuids
+                .split(',')
+                .map(str::trim)
+                .filter(|uuid| !uuid.is_empty())
+                .collect(),
+        }
+    }
+}
+
+// Fields this module doesn't use, such as `urgency` and user defined
+// attributes, are ignored when reading.
+#[derive(Serialize, Deserialize, Debug)]
+struct Task {
+    uuid: String,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheduled: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recur: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depends: Option<Depends>,
+    /// The recurring template a recurring task is an instance of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    /// Which instance of its template a recurring task is, counting from 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    imask: Option<f64>,
+}
+
+// Taskwarrior only takes UUIDs, so items with other ids, such as ones
+// imported from calendars, get one made from their id. It's the same every
+// time, so they're still found when they come back.
+fn task_uuid(id: &str) -> String {
+    return match Uuid::parse_str(id) {
+        Ok(uuid) => uuid.to_string(),
+        Err(_) => Uuid::new_v5(&Uuid::NAMESPACE_OID, id.as_bytes()).to_string(),
+    };
+}
+
+fn format_timestamp(instant: &DateTime<Utc>) -> String {
+    return instant.format("%Y%m%dT%H%M%SZ").to_string();
+}
+
+// Taskwarrior 3 also reads and writes RFC 3339 timestamps.
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, AppError> {
+    return NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .map(|instant| Utc.from_utc_datetime(&instant))
+        .or_else(|_| timestamp::parse_instant(value))
+        .map_err(|_| AppError::Validation(format!("{} is not a Taskwarrior date", value)));
+}
+
+fn format_priority(priority: Priority) -> Option<String> {
+    match priority {
+        Priority::None => None,
+        Priority::Low => Some(String::from("L")),
+        Priority::Medium => Some(String::from("M")),
+        Priority::High | Priority::Urgent => Some(String::from("H")),
+    }
+}
+
+fn parse_priority(value: Option<&str>) -> Result<Priority, AppError> {
+    match value {
+        None | Some("") => Ok(Priority::None),
+        Some("L") => Ok(Priority::Low),
+        Some("M") => Ok(Priority::Medium),
+        Some("H") => Ok(Priority::High),
+        Some(value) => Err(AppError::Validation(format!("{} is not H, M or L", value))),
+    }
+}
+
+fn format_recur(recurrence: &str) -> Result<Option<String>, AppError> {
+    let rule = Rule::parse(recurrence)?;
+
+    if rule.count.is_some() || rule.until.is_some() {
+        return Ok(None);
+    }
+
+    let is_weekdays = rule.frequency == Frequency::Weekly
+        && rule.interval == 1
+        && rule.by_day.len() == WEEKDAYS.len()
+        && rule
+            .by_day
+            .iter()
+            .all(|by_day| by_day.ordinal.is_none() && WEEKDAYS.contains(&by_day.weekday));
+
+    if is_weekdays {
+        return Ok(Some(String::from("weekdays")));
+    }
+
+    if !rule.by_day.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(match (rule.frequency, rule.interval) {
+        (Frequency::Daily, 1) => String::from("daily"),
+        (Frequency::Weekly, 1) => String::from("weekly"),
+        (Frequency::Monthly, 1) => String::from("monthly"),
+        (Frequency::Yearly, 1) => String::from("yearly"),
+        (Frequency::Daily, interval) => format!("{}d", interval),
+        (Frequency::Weekly, interval) => format!("{}w", interval),
+        (Frequency::Monthly, interval) => format!("{}mo", interval),
+        (Frequency::Yearly, interval) => format!("{}y", interval),
+    }));
+}
+
+// Taskwarrior's recurrences are durations, either named like `weekly` or a
+// number and a unit like `2w`.
+fn parse_recur(value: &str) -> Result<Rule, AppError> {
+    let invalid = || AppError::Validation(format!("Recurring every {} is not supported", value));
+
+    let rule = |frequency, interval| Rule {
+        frequency,
+        interval,
+        by_day: Vec::new(),
+        count: None,
+        until: None,
+    };
+
+    let value = value.trim().to_lowercase();
+
+    match value.as_str() {
+        "daily" | "day" => return Ok(rule(Frequency::Daily, 1)),
+        "weekly" | "week" => return Ok(rule(Frequency::Weekly, 1)),
+        "biweekly" | "fortnight" => return Ok(rule(Frequency::Weekly, 2)),
+        "monthly" | "month" => return Ok(rule(Frequency::Monthly, 1)),
+        "quarterly" => return Ok(rule(Frequency::Monthly, 3)),
+        "semiannual" => return Ok(rule(Frequency::Monthly, 6)),
+        "yearly" | "year" | "annual" => return Ok(rule(Frequency::Yearly, 1)),
+        "biannual" | "biyearly" => return Ok(rule(Frequency::Yearly, 2)),
+        "weekdays" => {
+            return Ok(Rule {
+                by_day: WEEKDAYS
+                    .iter()
+                    .map(|weekday| ByDay {
+                        ordinal: None,
+                        weekday: *weekday,
+                    })
+                    .collect(),
+                ..rule(Frequency::Weekly, 1)
+            })
+        }
+        _ => {}
+    }
+
+    let unit = value.trim_start_matches(|c: char| c.is_ascii_digit());
+    let interval = match &value[..value.len() - unit.len()] {
+        "" => 1,
+        number => number
+            .parse::<u32>()
+            .ok()
+            .filter(|interval| *interval > 0)
+            .ok_or_else(invalid)?,
+    };
+
+    match unit.trim() {
+        "d" | "day" | "days" => Ok(rule(Frequency::Daily, interval)),
+        "w" | "wk" | "wks" | "week" | "weeks" => Ok(rule(Frequency::Weekly, interval)),
+        "mo" | "mos" | "month" | "months" => Ok(rule(Frequency::Monthly, interval)),
+        "q" | "qtr" | "qtrs" | "quarter" | "quarters" => Ok(rule(
+            Frequency::Monthly,
+            interval.checked_mul(3).ok_or_else(invalid)?,
+        )),
+        "y" | "yr" | "yrs" | "year" | "years" => Ok(rule(Frequency::Yearly, interval)),
+        _ => Err(invalid()),
+    }
+}
+
+// Taskwarrior's dates are instants, so dates without a time are midnight in
+// `timezone`.
+fn format_date(date: NaiveDate, time: Option<NaiveTime>, timezone: Tz) -> String {
+    let instant = time
+        .and_then(|time| {
+            timezone
+                .from_local_datetime(&date.and_time(time))
+                .earliest()
+        })
+        .map(|instant| instant.with_timezone(&Utc))
+        .unwrap_or_else(|| timestamp::start_of_day(date, timezone));
+
+    return format_timestamp(&instant);
+}
+
+fn make_task(
+    todo_item: &TodoItemRecord,
+    project: Option<&str>,
+    tags: &[&str],
+    children: &[&str],
+    timezone: Tz,
+) -> Result<Task, AppError> {
+    let (status, end) = if let Some(deleted_at) = &todo_item.deleted_at {
+        ("deleted", Some(deleted_at))
+    } else if todo_item.is_completed {
+        ("completed", todo_item.completed_at.as_ref())
+    } else {
+        ("pending", None)
+    };
+
+    return Ok(Task {
+        uuid: task_uuid(&todo_item.id),
+        description: todo_item.title.clone(),
+        status: String::from(status),
+        entry: Some(format_timestamp(&todo_item.created_at)),
+        end: end.map(format_timestamp),
+        due: todo_item
+            .due_date
+            .map(|due_date| format_date(due_date, todo_item.due_time, timezone)),
+        scheduled: todo_item
+            .start_date
+            .map(|start_date| format_date(start_date, None, timezone)),
+        recur: match &todo_item.recurrence {
+            Some(recurrence) => format_recur(recurrence)?,
+            None => None,
+        },
+        project: project.map(String::from),
+        priority: format_priority(todo_item.priority),
+        // Tags can't have spaces in Taskwarrior.
+        tags: tags
+            .iter()
+            .map(|tag| tag.split_whitespace().collect::<Vec<&str>>().join("_"))
+            .collect(),
+        annotations: todo_item
+            .notes
+            .iter()
+            .map(|notes| Annotation {
+                entry: format_timestamp(&todo_item.created_at),
+                description: notes.clone(),
+            })
+            .collect(),
+        depends: if children.is_empty() {
+            None
+        } else {
+            Some(Depends::List(
+                children.iter().map(|child| task_uuid(child)).collect(),
+            ))
+        },
+        parent: None,
+        imask: None,
+    });
+}
+
+/// Writes every item to the file at `path` as a Taskwarrior task, with the
+/// ones in the trash deleted. Dates without a time are midnight in
+/// `timezone`.
+pub fn export(
+    connection: &mut Connection,
+    path: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let document = json::read(connection, now)?;
+
+    let projects: HashMap<&str, &str> = document
+        .projects
+        .iter()
+        .map(|project| (project.id.as_str(), project.name.as_str()))
+        .collect();
+    let tags: HashMap<&str, &str> = document
+        .tags
+        .iter()
+        .filter(|tag| tag.deleted_at.is_none())
+        .map(|tag| (tag.id.as_str(), tag.name.as_str()))
+        .collect();
+
+    let mut item_tags: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for todo_item_tag in &document.todo_item_tags {
+        if let Some(tag) = tags.get(todo_item_tag.tag_id.as_str()).copied() {
+            item_tags
+                .entry(todo_item_tag.todo_item_id.as_str())
+                .or_default()
+                .push(tag);
+        }
+    }
+
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for todo_item in &document.todo_items {
+        if let Some(parent_id) = &todo_item.parent_id {
+            children
+                .entry(parent_id.as_str())
+                .or_default()
+                .push(todo_item.id.as_str());
+        }
+    }
+
+    let mut tasks = Vec::new();
+
+    for todo_item in &document.todo_items {
+        let project = todo_item
+            .project_id
+            .as_deref()
+            .and_then(|project_id| projects.get(project_id).copied());
+        let mut tag_names = item_tags.remove(todo_item.id.as_str()).unwrap_or_default();
+
+        tag_names.sort_by_key(|tag_name| tag_name.to_lowercase());
+
+        tasks.push(make_task(
+            todo_item,
+            project,
+            &tag_names,
+            children
+                .get(todo_item.id.as_str())
+                .map_or(&[], Vec::as_slice),
+            timezone,
+        )?);
+    }
+
+    let mut text = serde_json::to_string_pretty(&tasks)?;
+    text.push('\n');
+
+    std::fs::write(path, text)?;
+
+    Ok(())
+}
+
+// `task export` writes a JSON array, but older versions and other tools write
+// one task per line.
+fn parse_tasks(text: &str) -> Result<Vec<Task>, AppError> {
+    if text.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(text)?);
+    }
+
+    let mut tasks = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim().trim_end_matches(',');
+
+        if !line.is_empty() {
+            tasks.push(serde_json::from_str(line)?);
+        }
+    }
+
+    return Ok(tasks);
+}
+
+// Updates the item with the same uuid if there is one. Everything a task can
+// say about an item is taken from it, so a field that's missing is cleared,
+// but the item keeps its description and position, which tasks don't have.
+fn parse_task(
+    items: &mut NewItems,
+    existing: &mut HashMap<String, TodoItemRecord>,
+    parent_ids: &HashMap<String, String>,
+    task: Task,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let uuid = Uuid::parse_str(task.uuid.trim())
+        .map_err(|_| AppError::Validation(format!("{} is not a UUID", task.uuid)))?
+        .to_string();
+
+    let title = task.description.trim();
+
+    if title.is_empty() {
+        return Err(AppError::Validation(String::from(
+            "The task has no description",
+        )));
+    }
+
+    let mut todo_item = match existing.remove(&uuid) {
+        Some(todo_item) => todo_item,
+        None => {
+            let created_at = match &task.entry {
+                Some(entry) => parse_timestamp(entry)?,
+                None => now,
+            };
+            let mut todo_item = TodoItemRecord::new(String::from(title), created_at, timezone);
+
+            todo_item.id = uuid.clone();
+            todo_item
+        }
+    };
+
+    todo_item.title = String::from(title);
+
+    let end = task.end.as_deref().map(parse_timestamp).transpose()?;
+
+    match task.status.as_str() {
+        "pending" | "waiting" => {
+            todo_item.is_completed = false;
+            todo_item.completed_at = None;
+            todo_item.completed_timezone = None;
+            todo_item.deleted_at = None;
+        }
+        "completed" => {
+            let completed_at = end.unwrap_or(now);
+
+            // Times in the file only have seconds, so an item completed when
+            // the task says it was keeps the exact time it has.
+            if !todo_item.is_completed
+                || todo_item.completed_at.map(|instant| instant.timestamp())
+                    != Some(completed_at.timestamp())
+            {
+                todo_item.completed_at = Some(completed_at);
+                todo_item.completed_timezone = Some(String::from(timezone.name()));
+            }
+
+            todo_item.is_completed = true;
+            todo_item.deleted_at = None;
+        }
+        "deleted" => {
+            if todo_item.deleted_at.is_none() {
+                todo_item.deleted_at = Some(end.unwrap_or(now));
+            }
+        }
+        status => {
+            return Err(AppError::Validation(format!(
+                "{} is not a Taskwarrior status",
+                status
+            )))
+        }
+    }
+
+    match &task.due {
+        Some(due) => {
+            let due = parse_timestamp(due)?.with_timezone(&timezone).naive_local();
+
+            todo_item.due_date = Some(due.date());
+            todo_item.due_time =
+                Some(due.time()).filter(|time| time.num_seconds_from_midnight() != 0);
+        }
+        None => {
+            todo_item.due_date = None;
+            todo_item.due_time = None;
+        }
+    }
+
+    todo_item.start_date = match &task.scheduled {
+        Some(scheduled) => Some(
+            parse_timestamp(scheduled)?
+                .with_timezone(&timezone)
+                .date_naive(),
+        ),
+        None => None,
+    };
+
+    // Instances of the same template are occurrences of the same series.
+    match &task.recur {
+        Some(recur) => {
+            todo_item.recurrence = Some(parse_recur(recur)?.to_string());
+            todo_item
+                .recurrence_mode
+                .get_or_insert(RecurrenceMode::FromDueDate);
+
+            let series_id = task.parent.as_deref().unwrap_or(&uuid);
+            todo_item
+                .series_id
+                .get_or_insert_with(|| String::from(series_id));
+
+            let occurrence = match task.imask {
+                Some(imask) => (imask as u32).checked_add(1).ok_or_else(|| {
+                    AppError::Validation(format!("Instance {} is out of range", imask))
+                })?,
+                None => 1,
+            };
+            todo_item.occurrence.get_or_insert(occurrence);
+        }
+        // Rules Taskwarrior can't express are left out of exports, so a task
+        // without one only ends a rule it could have had.
+        None => {
+            let is_left_out = todo_item.recurrence.as_deref().map_or(false, |recurrence| {
+                matches!(format_recur(recurrence), Ok(None))
+            });
+
+            if !is_left_out {
+                todo_item.recurrence = None;
+                todo_item.recurrence_mode = None;
+            }
+        }
+    }
+
+    // Urgent items are exported as `H` too, so they stay urgent when it comes
+    // back.
+    let priority = parse_priority(task.priority.as_deref())?;
+
+    if priority != Priority::High || todo_item.priority != Priority::Urgent {
+        todo_item.priority = priority;
+    }
+
+    todo_item.project_id = task
+        .project
+        .as_deref()
+        .map(str::trim)
+        .filter(|project| !project.is_empty())
+        .map(|project| items.project_id(project));
+
+    let notes: Vec<&str> = task
+        .annotations
+        .iter()
+        .map(|annotation| annotation.description.as_str())
+        .collect();
+    todo_item.notes = Some(notes.join("\n")).filter(|notes| !notes.is_empty());
+
+    todo_item.parent_id = parent_ids.get(&uuid).cloned();
+
+    let tag_ids = task
+        .tags
+        .iter()
+        .map(|tag_name| items.tag_id(tag_name))
+        .collect();
+
+    return items.add(todo_item, tag_ids);
+}
+
+/// Reads the tasks in the file at `path` into the database. Items whose id
+/// matches a task's uuid are updated, and the rest are added after the items
+/// that are, creating the projects and tags they mention that don't exist
+/// yet. Dates are read in `timezone`.
+pub fn import(
+    connection: &mut Connection,
+    path: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<ImportSummary, AppError> {
+    let tasks: Vec<Task> = parse_tasks(&std::fs::read_to_string(path)?)?
+        .into_iter()
+        .filter(|task| task.status != "recurring")
+        .collect();
+
+    let mut existing: HashMap<String, TodoItemRecord> = json::read(connection, now)?
+        .todo_items
+        .into_iter()
+        .map(|todo_item| (task_uuid(&todo_item.id), todo_item))
+        .collect();
+
+    // The ids items will have, by uuid, so subtasks can refer to parents
+    // whether they're in the file, the database or both.
+    let mut ids: HashMap<String, String> = existing
+        .iter()
+        .map(|(uuid, todo_item)| (uuid.clone(), todo_item.id.clone()))
+        .collect();
+
+    for task in &tasks {
+        if let Ok(uuid) = Uuid::parse_str(task.uuid.trim()) {
+            ids.entry(uuid.to_string())
+                .or_insert_with(|| uuid.to_string());
+        }
+    }
+
+    // A task that depends on others is their parent. Subtasks can only have
+    // one, so the first task to depend on them is.
+    let mut parent_ids: HashMap<String, String> = HashMap::new();
+
+    for task in &tasks {
+        let parent_id = match Uuid::parse_str(task.uuid.trim())
+            .ok()
+            .and_then(|uuid| ids.get(&uuid.to_string()))
+        {
+            Some(parent_id) => parent_id,
+            None => continue,
+        };
+
+        for child in task.depends.iter().flat_map(Depends::uuids) {
+            if let Ok(child) = Uuid::parse_str(child) {
+                if ids.contains_key(&child.to_string()) && *parent_id != child.to_string() {
+                    parent_ids
+                        .entry(child.to_string())
+                        .or_insert_with(|| parent_id.clone());
+                }
+            }
+        }
+    }
+
+    let mut items = NewItems::new(connection, now)?;
+
+    for task in tasks {
+        let uuid = task.uuid.clone();
+
+        parse_task(&mut items, &mut existing, &parent_ids, task, timezone, now).map_err(
+            |error| match error {
+                AppError::Validation(message) => {
+                    AppError::Validation(format!("Task {}: {}", uuid, message))
+                }
+                error => error,
+            },
+        )?;
+    }
+
+    return items.import(connection, ImportMode::MergeById);
+}
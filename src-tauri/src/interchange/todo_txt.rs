@@ -0,0 +1,402 @@
+//! The todo.txt format (https://github.com/todotxt/todo.txt), one item per
+//! line:
+//!
+//! ```text
+//! x 2024-03-02 2024-03-01 Call the bank +Errands @phone due:2024-03-04 pri:B
+//! (A) 2024-03-01 Water the plants rec:+1w due:2024-03-08
+//! ```
+//!
+//! Priorities `A` to `D` are urgent to low, and completed items keep theirs in
+//! `pri:`. The last `+project` is the item's project, and every `@context` is
+//! one of its tags. Spaces in their names are written as underscores. Besides
+//! `due:` and `pri:`, items can have `time:` for the time they're due, `t:` for
+//! their start date and `rec:` for how they recur, either in the usual short
+//! form like `2w` or as an RRULE, with a leading `+` when the next occurrence
+//! is counted from the due date rather than from completion. Any other
+//! `key:value` word is part of the title. Descriptions, notes and subtasks
+//! have no place in todo.txt and are left out.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::interchange::json::{self, ImportMode, TodoItemRecord};
+use crate::interchange::{ImportSummary, NewItems};
+use crate::models::todo_item::Priority;
+use crate::recurrence::{Frequency, RecurrenceMode, Rule};
+use crate::timestamp;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+fn priority_to_letter(priority: Priority) -> Option<char> {
+    match priority {
+        Priority::None => None,
+        Priority::Low => Some('D'),
+        Priority::Medium => Some('C'),
+        Priority::High => Some('B'),
+        Priority::Urgent => Some('A'),
+    }
+}
+
+// Priorities after D are less urgent still, which is as low as items go here.
+fn priority_from_letter(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::Urgent),
+        'B' => Some(Priority::High),
+        'C' => Some(Priority::Medium),
+        'D'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut letters = letter.chars();
+
+    match (letters.next(), letters.next()) {
+        (Some(letter), None) => priority_from_letter(letter),
+        _ => None,
+    }
+}
+
+fn parse_project(word: &str) -> Option<&str> {
+    return word.strip_prefix('+').filter(|name| !name.is_empty());
+}
+
+fn is_date(word: &str) -> bool {
+    return word.len() == 10 && NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok();
+}
+
+fn format_name(name: &str) -> String {
+    return name.split_whitespace().collect::<Vec<&str>>().join("_");
+}
+
+fn frequency_unit(frequency: Frequency) -> char {
+    match frequency {
+        Frequency::Daily => 'd',
+        Frequency::Weekly => 'w',
+        Frequency::Monthly => 'm',
+        Frequency::Yearly => 'y',
+    }
+}
+
+fn format_recurrence(recurrence: &str, mode: Option<RecurrenceMode>) -> Result<String, AppError> {
+    let rule = Rule::parse(recurrence)?;
+
+    let value = if rule.by_day.is_empty() && rule.count.is_none() && rule.until.is_none() {
+        format!("{}{}", rule.interval, frequency_unit(rule.frequency))
+    } else {
+        rule.to_string()
+    };
+
+    return Ok(match mode {
+        Some(RecurrenceMode::FromDueDate) => format!("+{}", value),
+        _ => value,
+    });
+}
+
+fn parse_recurrence(value: &str) -> Result<(Rule, RecurrenceMode), AppError> {
+    let (rule, mode) = match value.strip_prefix('+') {
+        Some(rule) => (rule, RecurrenceMode::FromDueDate),
+        None => (value, RecurrenceMode::FromCompletion),
+    };
+
+    // The short form is a number followed by the unit, such as `2w`.
+    let short = rule.char_indices().last().and_then(|(index, unit)| {
+        let frequency = match unit {
+            'd' => Frequency::Daily,
+            'w' => Frequency::Weekly,
+            'm' => Frequency::Monthly,
+            'y' => Frequency::Yearly,
+            _ => return None,
+        };
+        let interval = rule[..index]
+            .parse::<u32>()
+            .ok()
+            .filter(|interval| *interval > 0)?;
+
+        Some(Rule {
+            frequency,
+            interval,
+            by_day: Vec::new(),
+            count: None,
+            until: None,
+        })
+    });
+
+    if let Some(short) = short {
+        return Ok((short, mode));
+    }
+
+    return Ok((Rule::parse(rule)?, mode));
+}
+
+fn format_line(
+    todo_item: &TodoItemRecord,
+    project: Option<&str>,
+    tags: &[&str],
+) -> Result<String, AppError> {
+    let mut words: Vec<String> = Vec::new();
+
+    let created_timezone = timestamp::parse_timezone(&todo_item.created_timezone)?;
+    let created_on = todo_item
+        .created_at
+        .with_timezone(&created_timezone)
+        .date_naive();
+
+    match (todo_item.is_completed, todo_item.completed_at) {
+        (true, Some(completed_at)) => {
+            let completed_timezone = match &todo_item.completed_timezone {
+                Some(completed_timezone) => timestamp::parse_timezone(completed_timezone)?,
+                None => created_timezone,
+            };
+
+            words.push(String::from("x"));
+            words.push(timestamp::date_to_sql(
+                &completed_at.with_timezone(&completed_timezone).date_naive(),
+            ));
+        }
+        (true, None) => words.push(String::from("x")),
+        (false, _) => {
+            if let Some(letter) = priority_to_letter(todo_item.priority) {
+                words.push(format!("({})", letter));
+            }
+        }
+    }
+
+    words.push(timestamp::date_to_sql(&created_on));
+    words.extend(todo_item.title.split_whitespace().map(String::from));
+
+    if let Some(project) = project {
+        words.push(format!("+{}", format_name(project)));
+    }
+
+    for tag in tags {
+        words.push(format!("@{}", format_name(tag)));
+    }
+
+    if let Some(due_date) = todo_item.due_date {
+        words.push(format!("due:{}", timestamp::date_to_sql(&due_date)));
+    }
+
+    if let Some(due_time) = todo_item.due_time {
+        words.push(format!("time:{}", timestamp::time_to_sql(&due_time)));
+    }
+
+    if let Some(start_date) = todo_item.start_date {
+        words.push(format!("t:{}", timestamp::date_to_sql(&start_date)));
+    }
+
+    if let Some(recurrence) = &todo_item.recurrence {
+        words.push(format!(
+            "rec:{}",
+            format_recurrence(recurrence, todo_item.recurrence_mode)?
+        ));
+    }
+
+    if todo_item.is_completed {
+        if let Some(letter) = priority_to_letter(todo_item.priority) {
+            words.push(format!("pri:{}", letter));
+        }
+    }
+
+    return Ok(words.join(" "));
+}
+
+/// Writes every item that isn't in the trash or a subtask to the file at
+/// `path`, in order.
+pub fn export(connection: &mut Connection, path: &str, now: DateTime<Utc>) -> Result<(), AppError> {
+    let document = json::read(connection, now)?;
+
+    let projects: HashMap<&str, &str> = document
+        .projects
+        .iter()
+        .map(|project| (project.id.as_str(), project.name.as_str()))
+        .collect();
+    let tags: HashMap<&str, &str> = document
+        .tags
+        .iter()
+        .filter(|tag| tag.deleted_at.is_none())
+        .map(|tag| (tag.id.as_str(), tag.name.as_str()))
+        .collect();
+
+    let mut item_tags: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for todo_item_tag in &document.todo_item_tags {
+        if let Some(tag) = tags.get(todo_item_tag.tag_id.as_str()).copied() {
+            item_tags
+                .entry(todo_item_tag.todo_item_id.as_str())
+                .or_default()
+                .push(tag);
+        }
+    }
+
+    let mut lines = Vec::new();
+
+    for todo_item in &document.todo_items {
+        if todo_item.deleted_at.is_some() || todo_item.parent_id.is_some() {
+            continue;
+        }
+
+        let project = todo_item
+            .project_id
+            .as_deref()
+            .and_then(|project_id| projects.get(project_id).copied());
+        let mut tag_names = item_tags.remove(todo_item.id.as_str()).unwrap_or_default();
+
+        tag_names.sort_by_key(|tag_name| tag_name.to_lowercase());
+
+        lines.push(format_line(todo_item, project, &tag_names)?);
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+
+    std::fs::write(path, text)?;
+
+    Ok(())
+}
+
+// Items without a creation date were created when they're imported, and
+// dates become the start of the day in `timezone`.
+fn parse_line(
+    items: &mut NewItems,
+    line: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let mut words = line.split_whitespace().peekable();
+
+    let is_completed = words.next_if_eq(&"x").is_some();
+    let mut priority = if is_completed {
+        None
+    } else {
+        words
+            .next_if(|word| parse_priority(word).is_some())
+            .and_then(parse_priority)
+    };
+
+    let first_date = words.next_if(|word| is_date(word));
+    let second_date = if is_completed && first_date.is_some() {
+        words.next_if(|word| is_date(word))
+    } else {
+        None
+    };
+
+    let (completed_on, created_on) = if is_completed {
+        (first_date, second_date)
+    } else {
+        (None, first_date)
+    };
+
+    let created_at = match created_on {
+        Some(created_on) => timestamp::start_of_day(timestamp::parse_date(created_on)?, timezone),
+        None => now,
+    };
+
+    let words: Vec<&str> = words.collect();
+    let project_index = words.iter().rposition(|word| parse_project(word).is_some());
+
+    let mut title = Vec::new();
+    let mut tag_names = Vec::new();
+    let mut due_date = None;
+    let mut due_time = None;
+    let mut start_date = None;
+    let mut recurrence = None;
+
+    for (index, word) in words.iter().copied().enumerate() {
+        if Some(index) == project_index {
+            continue;
+        }
+
+        if let Some(name) = word.strip_prefix('@').filter(|name| !name.is_empty()) {
+            tag_names.push(name);
+            continue;
+        }
+
+        match word.split_once(':') {
+            Some(("due", value)) => due_date = Some(timestamp::parse_date(value)?),
+            Some(("time", value)) => due_time = Some(timestamp::parse_time(value)?),
+            Some(("t", value)) => start_date = Some(timestamp::parse_date(value)?),
+            Some(("rec", value)) => recurrence = Some(parse_recurrence(value)?),
+            Some(("pri", value)) if priority.is_none() => {
+                priority = Some(parse_priority(&format!("({})", value)).ok_or_else(|| {
+                    AppError::Validation(format!("{} is not a priority from A to Z", value))
+                })?)
+            }
+            _ => title.push(word),
+        }
+    }
+
+    if title.is_empty() {
+        return Err(AppError::Validation(String::from("The item has no title")));
+    }
+
+    if due_time.is_some() && due_date.is_none() {
+        return Err(AppError::Validation(String::from(
+            "The item has a time: but no due: date",
+        )));
+    }
+
+    let mut todo_item = TodoItemRecord::new(title.join(" "), created_at, timezone);
+
+    todo_item.is_completed = is_completed;
+    todo_item.priority = priority.unwrap_or(Priority::None);
+    todo_item.due_date = due_date;
+    todo_item.due_time = due_time;
+    todo_item.start_date = start_date;
+
+    if is_completed {
+        todo_item.completed_at = Some(match completed_on {
+            Some(completed_on) => {
+                timestamp::start_of_day(timestamp::parse_date(completed_on)?, timezone)
+            }
+            None => now,
+        });
+        todo_item.completed_timezone = Some(String::from(timezone.name()));
+    }
+
+    if let Some((rule, mode)) = recurrence {
+        todo_item.recurrence = Some(rule.to_string());
+        todo_item.recurrence_mode = Some(mode);
+        todo_item.series_id = Some(todo_item.id.clone());
+        todo_item.occurrence = Some(1);
+    }
+
+    todo_item.project_id = project_index
+        .and_then(|index| parse_project(words[index]))
+        .map(|project| items.project_id(project));
+
+    let tag_ids = tag_names
+        .into_iter()
+        .map(|tag_name| items.tag_id(tag_name))
+        .collect();
+
+    return items.add(todo_item, tag_ids);
+}
+
+/// Adds the items in the todo.txt file at `path` after the ones already in the
+/// database, creating the projects and tags they mention that don't exist yet.
+pub fn import(
+    connection: &mut Connection,
+    path: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<ImportSummary, AppError> {
+    let text = std::fs::read_to_string(path)?;
+    let mut items = NewItems::new(connection, now)?;
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        parse_line(&mut items, line, timezone, now).map_err(|error| match error {
+            AppError::Validation(message) => {
+                AppError::Validation(format!("Line {}: {}", index + 1, message))
+            }
+            error => error,
+        })?;
+    }
+
+    return items.import(connection, ImportMode::DuplicateAsNew);
+}
@@ -0,0 +1,405 @@
+//! Undo and redo for commands that change todo items and tags.
+//!
+//! While a command runs inside [`record`], temporary triggers on the journaled
+//! tables write the SQL that reverses each row they change to
+//! `journalChanges`, under one step in `journalSteps`. Undoing a step runs its
+//! changes backwards while the triggers record again, which leaves the step
+//! holding exactly what it takes to redo it.
+
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::timestamp;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlite::State;
+
+const TABLES: [&str; 3] = ["todoItems", "todoItemsTags", "tags"];
+
+/// How many steps can be undone.
+const MAX_STEPS: i64 = 100;
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStep {
+    pub id: i64,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The steps that would be undone and redone next, so the UI can label and
+/// disable its undo and redo actions.
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalState {
+    pub undo: Option<JournalStep>,
+    pub redo: Option<JournalStep>,
+}
+
+fn get_columns(connection: &mut Connection, table: &str) -> Result<Vec<String>, AppError> {
+    let mut columns = Vec::new();
+
+    let statement = connection.prepare(&format!("pragma table_info({})", table))?;
+
+    while let State::Row = statement.next()? {
+        columns.push(statement.read::<String>(1)?);
+    }
+
+    return Ok(columns);
+}
+
+// Builds an SQL expression that joins what `format` makes of each column with
+// `separator`.
+fn concat(columns: &[String], separator: &str, format: impl Fn(&str) -> String) -> String {
+    return columns
+        .iter()
+        .map(|column| format(column))
+        .collect::<Vec<String>>()
+        .join(&format!(" || '{}' || ", separator));
+}
+
+/// Creates the triggers that record changes. They're temporary, so they only
+/// exist on this connection and always match the current schema.
+pub fn install(connection: &mut Connection) -> Result<(), AppError> {
+    connection.execute(
+        "
+        create temp table journalRecording (
+          stepId INTEGER NOT NULL
+        )
+      ",
+    )?;
+
+    for table in TABLES {
+        let columns = get_columns(connection, table)?;
+
+        let names = columns.join(", ");
+        let values = concat(&columns, ", ", |column| format!("quote(old.{})", column));
+
+        // Updates only put back the columns they changed, so undoing them
+        // leaves alone what was changed since without being recorded. Each
+        // assignment starts with a separator, which `substr` drops from the
+        // first one.
+        let assignments = concat(&columns, "", |column| {
+            format!(
+                "case when old.{column} is not new.{column} \
+                 then ', {column} = ' || quote(old.{column}) else '' end",
+                column = column
+            )
+        });
+        let is_changed = columns
+            .iter()
+            .map(|column| format!("old.{} is not new.{}", column, column))
+            .collect::<Vec<String>>()
+            .join(" or ");
+
+        let changes = [
+            (
+                "Insert",
+                String::from("1"),
+                format!("'delete from {} where id = ' || quote(new.id)", table),
+            ),
+            (
+                "Update",
+                is_changed,
+                format!(
+                    "'update {} set ' || substr({}, 3) || ' where id = ' || quote(new.id)",
+                    table, assignments
+                ),
+            ),
+            (
+                "Delete",
+                String::from("1"),
+                format!(
+                    "'insert into {} ({}) values (' || {} || ')'",
+                    table, names, values
+                ),
+            ),
+        ];
+
+        for (event, condition, sql) in changes {
+            connection.execute(&format!(
+                "
+                create temp trigger {table}Journal{event} after {event} on main.{table}
+                when exists (select 1 from journalRecording) and ({condition})
+                begin
+                  insert into journalChanges (stepId, sql)
+                  values ((select stepId from journalRecording), {sql});
+                end
+              ",
+                table = table,
+                event = event,
+                condition = condition,
+                sql = sql
+            ))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_recording(connection: &mut Connection) -> Result<bool, AppError> {
+    let statement = connection.prepare("select count(*) from journalRecording")?;
+
+    statement.next()?;
+
+    return Ok(statement.read::<i64>(0)? > 0);
+}
+
+fn start_recording(connection: &mut Connection, step_id: i64) -> Result<(), AppError> {
+    let statement = connection.prepare("insert into journalRecording (stepId) values (?)")?;
+
+    statement.bind(1, step_id)?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn stop_recording(connection: &mut Connection) -> Result<(), AppError> {
+    connection.prepare("delete from journalRecording")?.next()?;
+
+    Ok(())
+}
+
+fn record_step<T>(
+    connection: &mut Connection,
+    description: &str,
+    action: impl FnOnce(&mut Connection) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    // Doing something new makes whatever was undone impossible to redo.
+    connection
+        .prepare("delete from journalSteps where isUndone = 'true'")?
+        .next()?;
+
+    let statement = connection.prepare(
+        "
+        insert into journalSteps (
+          description,
+          createdAt,
+          isUndone
+        ) values (
+          ?,
+          ?,
+          'false'
+        )
+      ",
+    )?;
+
+    statement.bind(1, description)?;
+    statement.bind(2, &*timestamp::to_sql(&Utc::now()))?;
+
+    statement.next()?;
+
+    let statement = connection.prepare("select last_insert_rowid()")?;
+
+    statement.next()?;
+
+    let step_id = statement.read::<i64>(0)?;
+
+    start_recording(connection, step_id)?;
+    let value = action(connection)?;
+    stop_recording(connection)?;
+
+    let statement = connection.prepare(
+        "
+        delete from journalSteps
+        where
+          id = :id
+          and not exists (select 1 from journalChanges where stepId = :id)
+      ",
+    )?;
+
+    statement.bind_by_name(":id", step_id)?;
+
+    statement.next()?;
+
+    let statement = connection.prepare(
+        "
+        delete from journalSteps
+        where id not in (
+          select id
+          from journalSteps
+          order by id desc
+          limit ?
+        )
+      ",
+    )?;
+
+    statement.bind(1, MAX_STEPS)?;
+
+    statement.next()?;
+
+    return Ok(value);
+}
+
+/// Runs `action` as one step that can be undone, described by `description`.
+/// Actions recorded while another is being recorded become part of its step,
+/// so commands that change many items at once are undone all at once.
+pub fn record<T>(
+    connection: &mut Connection,
+    description: &str,
+    action: impl FnOnce(&mut Connection) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    if is_recording(connection)? {
+        return action(connection);
+    }
+
+    // If the action fails, the step is rolled back along with it.
+    let value = connection.savepoint(|connection| record_step(connection, description, action))?;
+
+    connection.notify_written();
+
+    return Ok(value);
+}
+
+fn get_step(connection: &mut Connection, is_undone: bool) -> Result<Option<JournalStep>, AppError> {
+    // The step to undo is the last one done, and the step to redo the first
+    // one undone.
+    let statement = connection.prepare(if is_undone {
+        "
+        select
+          id,
+          description,
+          createdAt
+        from journalSteps
+        where isUndone = 'true'
+        order by id
+        limit 1
+      "
+    } else {
+        "
+        select
+          id,
+          description,
+          createdAt
+        from journalSteps
+        where isUndone = 'false'
+        order by id desc
+        limit 1
+      "
+    })?;
+
+    if let State::Row = statement.next()? {
+        return Ok(Some(JournalStep {
+            id: statement.read::<i64>(0)?,
+            description: statement.read::<String>(1)?,
+            created_at: timestamp::from_sql(&statement.read::<String>(2)?)?,
+        }));
+    }
+
+    Ok(None)
+}
+
+fn flip_step(connection: &mut Connection, step: &JournalStep) -> Result<(), AppError> {
+    let mut changes = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select sql
+        from journalChanges
+        where stepId = ?
+        order by id desc
+      ",
+    )?;
+
+    statement.bind(1, step.id)?;
+
+    while let State::Row = statement.next()? {
+        changes.push(statement.read::<String>(0)?);
+    }
+
+    let statement = connection.prepare("delete from journalChanges where stepId = ?")?;
+
+    statement.bind(1, step.id)?;
+
+    statement.next()?;
+
+    // Changes run in reverse, so a row can briefly reference one that hasn't
+    // been put back yet. The constraints are checked when the savepoint is
+    // released instead.
+    connection.execute("pragma defer_foreign_keys = on")?;
+
+    start_recording(connection, step.id)?;
+
+    // Every change was recorded for a row that existed then, so one that no
+    // longer finds its row means the step doesn't apply anymore.
+    for change in changes {
+        connection.execute(&change)?;
+
+        if connection.changes()? == 0 {
+            return Err(AppError::Conflict(format!("{} changes nothing", change)));
+        }
+    }
+
+    stop_recording(connection)?;
+
+    let statement = connection.prepare(
+        "
+        update journalSteps
+        set isUndone = case isUndone when 'true' then 'false' else 'true' end
+        where id = ?
+      ",
+    )?;
+
+    statement.bind(1, step.id)?;
+
+    statement.next()?;
+
+    Ok(())
+}
+
+fn apply(connection: &mut Connection, is_undone: bool) -> Result<Option<JournalStep>, AppError> {
+    let step = match get_step(connection, is_undone)? {
+        Some(step) => step,
+        None => return Ok(None),
+    };
+
+    match connection.savepoint(|connection| flip_step(connection, &step)) {
+        Ok(()) => {
+            connection.notify_written();
+
+            return Ok(Some(step));
+        }
+        // What the step changed was changed since in a way that wasn't
+        // recorded, so it would fail the same way every time. Dropping it
+        // lets the steps behind it be undone and redone.
+        Err(AppError::Conflict(_)) => {
+            let statement = connection.prepare("delete from journalSteps where id = ?")?;
+
+            statement.bind(1, step.id)?;
+
+            statement.next()?;
+
+            return Err(AppError::Conflict(format!(
+                "{} can no longer be {}, since what it changed is gone",
+                step.description,
+                if is_undone { "redone" } else { "undone" }
+            )));
+        }
+        Err(error) => return Err(error),
+    }
+}
+
+/// Undoes the last step that was done, if there is one, and returns it.
+pub fn undo(connection: &mut Connection) -> Result<Option<JournalStep>, AppError> {
+    return apply(connection, false);
+}
+
+/// Redoes the last step that was undone, if there is one, and returns it.
+pub fn redo(connection: &mut Connection) -> Result<Option<JournalStep>, AppError> {
+    return apply(connection, true);
+}
+
+/// Forgets every step, for changes that can't be undone and that the steps
+/// before them might no longer apply to.
+pub fn clear(connection: &mut Connection) -> Result<(), AppError> {
+    connection.prepare("delete from journalSteps")?.next()?;
+
+    Ok(())
+}
+
+pub fn get_state(connection: &mut Connection) -> Result<JournalState, AppError> {
+    return Ok(JournalState {
+        undo: get_step(connection, false)?,
+        redo: get_step(connection, true)?,
+    });
+}
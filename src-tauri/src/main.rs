@@ -2,36 +2,229 @@
     all(not(debug_assertions), target_os = "windows"),
     windows_subsystem = "windows"
 )]
-#[cfg(any(windows, target_os = "macos"))]
+use chrono::Utc;
 use tauri::Manager;
 use window_shadows::set_shadow;
 
 mod controllers;
 pub mod database;
+pub mod error;
+pub mod filter;
+pub mod interchange;
+pub mod journal;
 pub mod models;
+pub mod rank;
+pub mod recurrence;
+pub mod search;
+pub mod timestamp;
 
 fn main() {
+    #[cfg(debug_assertions)]
+    tauri_specta::ts::export(
+        specta::collect_types![
+            controllers::todo_items::get_todo_items,
+            controllers::todo_items::get_project_todo_items,
+            controllers::todo_items::search_todo_items,
+            controllers::todo_items::create_todo_item,
+            controllers::todo_items::complete_todo_item,
+            controllers::todo_items::complete_todo_items,
+            controllers::todo_items::uncomplete_todo_item,
+            controllers::todo_items::update_todo_item,
+            controllers::todo_items::set_todo_item_due_date,
+            controllers::todo_items::set_todo_item_start_date,
+            controllers::todo_items::set_todo_item_priority,
+            controllers::todo_items::set_todo_item_recurrence,
+            controllers::todo_items::skip_todo_item_occurrence,
+            controllers::todo_items::end_todo_item_recurrence,
+            controllers::todo_items::move_todo_item,
+            controllers::todo_items::get_todo_item_children,
+            controllers::todo_items::set_todo_item_parent,
+            controllers::todo_items::set_todo_item_project,
+            controllers::todo_items::set_todo_item_completes_with_children,
+            controllers::todo_items::get_todo_items_due_on,
+            controllers::todo_items::get_overdue_todo_items,
+            controllers::todo_items::get_upcoming_todo_items,
+            controllers::todo_items::get_undated_todo_items,
+            controllers::todo_items::delete_todo_item,
+            controllers::todo_items::delete_todo_items,
+            controllers::todo_items::restore_todo_item,
+            controllers::todo_items::get_todo_item_history,
+            controllers::todo_items::restore_todo_item_field,
+            controllers::todo_items::add_tag_to_todo_item,
+            controllers::todo_items::remove_tag_from_todo_item,
+            controllers::todo_items::get_todo_item_tags,
+            controllers::todo_items::get_tags_for_items,
+            controllers::tags::get_tags,
+            controllers::tags::create_tag,
+            controllers::tags::update_tag,
+            controllers::tags::delete_tag,
+            controllers::tags::restore_tag,
+            controllers::projects::get_projects,
+            controllers::projects::create_project,
+            controllers::projects::update_project,
+            controllers::projects::set_project_archived,
+            controllers::projects::delete_project,
+            controllers::smart_lists::get_smart_lists,
+            controllers::smart_lists::create_smart_list,
+            controllers::smart_lists::update_smart_list,
+            controllers::smart_lists::delete_smart_list,
+            controllers::smart_lists::evaluate_smart_list,
+            controllers::smart_lists::evaluate_filter,
+            controllers::trash::list_trash,
+            controllers::trash::empty_trash,
+            controllers::trash::get_trash_retention_days,
+            controllers::trash::set_trash_retention_days,
+            controllers::journal::undo,
+            controllers::journal::redo,
+            controllers::journal::get_journal_state,
+            controllers::interchange::export_data,
+            controllers::interchange::import_data,
+            controllers::interchange::export_todo_txt,
+            controllers::interchange::import_todo_txt,
+            controllers::interchange::export_ical,
+            controllers::interchange::import_ical,
+            controllers::interchange::get_ical_feed,
+            controllers::interchange::set_ical_feed_enabled,
+            controllers::interchange::preview_csv,
+            controllers::interchange::import_csv,
+            controllers::interchange::export_csv,
+            controllers::interchange::render_markdown,
+            controllers::interchange::export_markdown,
+            controllers::interchange::get_default_markdown_template,
+            controllers::interchange::import_markdown,
+            controllers::interchange::export_taskwarrior,
+            controllers::interchange::import_taskwarrior,
+        ],
+        "../src/generated/bindings.ts",
+    )
+    .unwrap();
+
     tauri::Builder::default()
         .setup(|app| {
             let window = app.get_window("main").unwrap();
             set_shadow(&window, true).unwrap();
 
+            let database = database::Database::open(&app.handle())?;
+            models::trash::purge_expired(&mut database.lock(), Utc::now())?;
+            let write_signal = database.write_signal();
+            app.manage(database);
+
+            let mut feed = interchange::ical::Feed::new(&database::data_dir(&app.handle())?);
+            let app_handle = app.handle();
+
+            // The feed is refreshed when the app starts and then after every
+            // write, and tried again later when refreshing fails.
+            std::thread::spawn(move || {
+                let mut retry_interval = None;
+                let mut last_error = None;
+
+                loop {
+                    let database = app_handle.state::<database::Database>();
+
+                    match feed.refresh(&mut database.lock(), Utc::now()) {
+                        Ok(()) => {
+                            retry_interval = None;
+                            last_error = None;
+                        }
+                        // Failures tend to repeat, such as when the directory
+                        // is read-only, so each is logged once and retried
+                        // less and less often.
+                        Err(error) => {
+                            let message = error.to_string();
+
+                            if last_error.as_ref() != Some(&message) {
+                                eprintln!("Could not update the calendar feed: {}", message);
+                            }
+
+                            last_error = Some(message);
+                            retry_interval = Some(retry_interval.map_or(
+                                interchange::ical::FEED_RETRY_INTERVAL,
+                                |interval: std::time::Duration| {
+                                    (interval * 2).min(interchange::ical::FEED_MAX_RETRY_INTERVAL)
+                                },
+                            ));
+                        }
+                    }
+
+                    write_signal.wait(retry_interval);
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             controllers::todo_items::get_todo_items,
+            controllers::todo_items::get_project_todo_items,
+            controllers::todo_items::search_todo_items,
             controllers::todo_items::create_todo_item,
             controllers::todo_items::complete_todo_item,
+            controllers::todo_items::complete_todo_items,
             controllers::todo_items::uncomplete_todo_item,
             controllers::todo_items::update_todo_item,
+            controllers::todo_items::set_todo_item_due_date,
+            controllers::todo_items::set_todo_item_start_date,
+            controllers::todo_items::set_todo_item_priority,
+            controllers::todo_items::set_todo_item_recurrence,
+            controllers::todo_items::skip_todo_item_occurrence,
+            controllers::todo_items::end_todo_item_recurrence,
+            controllers::todo_items::move_todo_item,
+            controllers::todo_items::get_todo_item_children,
+            controllers::todo_items::set_todo_item_parent,
+            controllers::todo_items::set_todo_item_project,
+            controllers::todo_items::set_todo_item_completes_with_children,
+            controllers::todo_items::get_todo_items_due_on,
+            controllers::todo_items::get_overdue_todo_items,
+            controllers::todo_items::get_upcoming_todo_items,
+            controllers::todo_items::get_undated_todo_items,
             controllers::todo_items::delete_todo_item,
+            controllers::todo_items::delete_todo_items,
+            controllers::todo_items::restore_todo_item,
+            controllers::todo_items::get_todo_item_history,
+            controllers::todo_items::restore_todo_item_field,
             controllers::todo_items::add_tag_to_todo_item,
             controllers::todo_items::remove_tag_from_todo_item,
             controllers::todo_items::get_todo_item_tags,
+            controllers::todo_items::get_tags_for_items,
             controllers::tags::get_tags,
             controllers::tags::create_tag,
             controllers::tags::update_tag,
             controllers::tags::delete_tag,
+            controllers::tags::restore_tag,
+            controllers::projects::get_projects,
+            controllers::projects::create_project,
+            controllers::projects::update_project,
+            controllers::projects::set_project_archived,
+            controllers::projects::delete_project,
+            controllers::smart_lists::get_smart_lists,
+            controllers::smart_lists::create_smart_list,
+            controllers::smart_lists::update_smart_list,
+            controllers::smart_lists::delete_smart_list,
+            controllers::smart_lists::evaluate_smart_list,
+            controllers::smart_lists::evaluate_filter,
+            controllers::trash::list_trash,
+            controllers::trash::empty_trash,
+            controllers::trash::get_trash_retention_days,
+            controllers::trash::set_trash_retention_days,
+            controllers::journal::undo,
+            controllers::journal::redo,
+            controllers::journal::get_journal_state,
+            controllers::interchange::export_data,
+            controllers::interchange::import_data,
+            controllers::interchange::export_todo_txt,
+            controllers::interchange::import_todo_txt,
+            controllers::interchange::export_ical,
+            controllers::interchange::import_ical,
+            controllers::interchange::get_ical_feed,
+            controllers::interchange::set_ical_feed_enabled,
+            controllers::interchange::preview_csv,
+            controllers::interchange::import_csv,
+            controllers::interchange::export_csv,
+            controllers::interchange::render_markdown,
+            controllers::interchange::export_markdown,
+            controllers::interchange::get_default_markdown_template,
+            controllers::interchange::import_markdown,
+            controllers::interchange::export_taskwarrior,
+            controllers::interchange::import_taskwarrior,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
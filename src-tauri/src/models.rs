@@ -1,2 +1,7 @@
+pub mod project;
+pub mod setting;
+pub mod smart_list;
 pub mod tag;
 pub mod todo_item;
+pub mod todo_item_event;
+pub mod trash;
@@ -0,0 +1,229 @@
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::journal;
+use serde::{Deserialize, Serialize};
+use sqlite::{State, Statement};
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub icon: Option<String>,
+    pub is_archived: bool,
+    pub sort_order: i64,
+}
+
+fn read(statement: &Statement) -> Result<Project, AppError> {
+    return Ok(Project {
+        id: statement.read::<String>(0)?,
+        name: statement.read::<String>(1)?,
+        color: statement.read::<String>(2)?,
+        icon: statement.read::<Option<String>>(3)?,
+        is_archived: statement.read::<String>(4)? == "true",
+        sort_order: statement.read::<i64>(5)?,
+    });
+}
+
+/// Every project, archived ones included, in the order they're listed in.
+pub fn get_all(connection: &mut Connection) -> Result<Vec<Project>, AppError> {
+    let mut projects = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          name,
+          color,
+          icon,
+          isArchived,
+          sortOrder
+        from projects
+        order by sortOrder, name
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        projects.push(read(statement)?)
+    }
+
+    Ok(projects)
+}
+
+pub fn get(connection: &mut Connection, id: String) -> Result<Project, AppError> {
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          name,
+          color,
+          icon,
+          isArchived,
+          sortOrder
+        from projects
+        where
+            id = ?
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+
+    if let State::Row = statement.next()? {
+        return read(statement);
+    }
+
+    Err(AppError::NotFound(format!("Project {} does not exist", id)))
+}
+
+/// Creates a project at the end of the list.
+pub fn create(
+    connection: &mut Connection,
+    id: String,
+    name: String,
+    color: String,
+    icon: Option<String>,
+) -> Result<Project, AppError> {
+    let statement = connection.prepare(
+        "
+        insert into projects (
+          id,
+          name,
+          color,
+          icon,
+          isArchived,
+          sortOrder
+        ) values (
+          ?,
+          ?,
+          ?,
+          ?,
+          'false',
+          (select coalesce(max(sortOrder) + 1, 0) from projects)
+        )
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+    statement.bind(2, &*name)?;
+    statement.bind(3, &*color)?;
+    statement.bind(4, icon.as_deref())?;
+
+    statement.next()?;
+
+    return get(connection, id);
+}
+
+pub fn update(
+    connection: &mut Connection,
+    id: String,
+    name: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
+    sort_order: Option<i64>,
+) -> Result<(), AppError> {
+    let mut conditions: Vec<String> = Vec::new();
+
+    let parameter_mapping = vec![
+        (
+            String::from("name"),
+            name.as_ref().map(|name| String::from(name)),
+        ),
+        (
+            String::from("color"),
+            color.as_ref().map(|color| String::from(color)),
+        ),
+        (
+            String::from("icon"),
+            icon.as_ref().map(|icon| String::from(icon)),
+        ),
+    ];
+
+    let mut bind_params: Vec<(String, String)> = Vec::new();
+
+    for (key, value) in parameter_mapping {
+        if let Some(value) = value {
+            conditions.push(format!("{} = :{}", key, key));
+            bind_params.push((key, value));
+        }
+    }
+
+    if sort_order.is_some() {
+        conditions.push(String::from("sortOrder = :sortOrder"));
+    }
+
+    if conditions.is_empty() {
+        return Err(AppError::Validation(String::from(
+            "At least one project field must be updated",
+        )));
+    }
+
+    let mut sql = vec![String::from("update projects set")];
+    sql.push(conditions.join(", "));
+    sql.push(String::from("where id = :id"));
+
+    let statement = connection.prepare(&sql.join(" "))?;
+
+    statement.bind_by_name(":id", &*id)?;
+
+    for (key, value) in bind_params {
+        statement.bind_by_name(format!(":{}", &key).as_str(), &*value)?;
+    }
+
+    if let Some(sort_order) = sort_order {
+        statement.bind_by_name(":sortOrder", sort_order)?;
+    }
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Project {} does not exist", id))?;
+
+    Ok(())
+}
+
+/// Archiving a project hides its items from every view but the project's own,
+/// without deleting them.
+pub fn set_archived(
+    connection: &mut Connection,
+    id: String,
+    is_archived: bool,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        update projects
+        set isArchived = ?
+        where id = ?
+      ",
+    )?;
+
+    statement.bind(1, is_archived.to_string().as_str())?;
+    statement.bind(2, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Project {} does not exist", id))?;
+
+    Ok(())
+}
+
+/// Deletes the project. Its items are kept and no longer belong to a project.
+pub fn delete(connection: &mut Connection, id: String) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        delete from projects
+        where id = ?
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Project {} does not exist", id))?;
+
+    // Moving items into the project can't be undone without it, and moving
+    // them out wasn't recorded.
+    journal::clear(connection)?;
+
+    Ok(())
+}
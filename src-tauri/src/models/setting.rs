@@ -0,0 +1,47 @@
+use crate::database::Connection;
+use crate::error::AppError;
+use sqlite::State;
+
+/// How many days deleted items and tags stay in the trash.
+pub const TRASH_RETENTION_DAYS: &str = "trashRetentionDays";
+
+/// Whether the calendar feed in the app data directory is kept up to date.
+pub const ICAL_FEED_ENABLED: &str = "icalFeedEnabled";
+
+pub fn get(connection: &mut Connection, key: &str) -> Result<String, AppError> {
+    let statement = connection.prepare(
+        "
+        select value
+        from settings
+        where key = ?
+      ",
+    )?;
+
+    statement.bind(1, key)?;
+
+    if let State::Row = statement.next()? {
+        return Ok(statement.read::<String>(0)?);
+    }
+
+    Err(AppError::NotFound(format!(
+        "Setting {} does not exist",
+        key
+    )))
+}
+
+pub fn set(connection: &mut Connection, key: &str, value: &str) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        insert into settings (key, value)
+        values (?, ?)
+        on conflict (key) do update set value = excluded.value
+      ",
+    )?;
+
+    statement.bind(1, key)?;
+    statement.bind(2, value)?;
+
+    statement.next()?;
+
+    Ok(())
+}
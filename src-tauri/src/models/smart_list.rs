@@ -0,0 +1,190 @@
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::filter;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlite::{State, Statement};
+
+/// A saved filter, such as `tag:work due<=+3d !completed`.
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartList {
+    pub id: String,
+    pub name: String,
+    pub filter: String,
+    pub sort_order: i64,
+}
+
+fn read(statement: &Statement) -> Result<SmartList, AppError> {
+    return Ok(SmartList {
+        id: statement.read::<String>(0)?,
+        name: statement.read::<String>(1)?,
+        filter: statement.read::<String>(2)?,
+        sort_order: statement.read::<i64>(3)?,
+    });
+}
+
+// Relative dates don't affect whether a filter is valid, so any day will do.
+pub fn validate(filter: &str) -> Result<(), AppError> {
+    filter::compile(filter, Utc::now().date_naive())?;
+    Ok(())
+}
+
+pub fn get_all(connection: &mut Connection) -> Result<Vec<SmartList>, AppError> {
+    let mut smart_lists = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          name,
+          filter,
+          sortOrder
+        from smartLists
+        order by sortOrder, name
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        smart_lists.push(read(statement)?)
+    }
+
+    Ok(smart_lists)
+}
+
+pub fn get(connection: &mut Connection, id: String) -> Result<SmartList, AppError> {
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          name,
+          filter,
+          sortOrder
+        from smartLists
+        where
+            id = ?
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+
+    if let State::Row = statement.next()? {
+        return read(statement);
+    }
+
+    Err(AppError::NotFound(format!(
+        "Smart list {} does not exist",
+        id
+    )))
+}
+
+/// Creates a smart list at the end of the list, if its filter is valid.
+pub fn create(
+    connection: &mut Connection,
+    id: String,
+    name: String,
+    filter: String,
+) -> Result<SmartList, AppError> {
+    validate(&filter)?;
+
+    let statement = connection.prepare(
+        "
+        insert into smartLists (
+          id,
+          name,
+          filter,
+          sortOrder
+        ) values (
+          ?,
+          ?,
+          ?,
+          (select coalesce(max(sortOrder) + 1, 0) from smartLists)
+        )
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+    statement.bind(2, &*name)?;
+    statement.bind(3, &*filter)?;
+
+    statement.next()?;
+
+    return get(connection, id);
+}
+
+pub fn update(
+    connection: &mut Connection,
+    id: String,
+    name: Option<String>,
+    filter: Option<String>,
+    sort_order: Option<i64>,
+) -> Result<(), AppError> {
+    if let Some(filter) = &filter {
+        validate(filter)?;
+    }
+
+    let mut conditions: Vec<String> = Vec::new();
+
+    let parameter_mapping = vec![
+        (String::from("name"), name),
+        (String::from("filter"), filter),
+    ];
+
+    let mut bind_params: Vec<(String, String)> = Vec::new();
+
+    for (key, value) in parameter_mapping {
+        if let Some(value) = value {
+            conditions.push(format!("{} = :{}", key, key));
+            bind_params.push((key, value));
+        }
+    }
+
+    if sort_order.is_some() {
+        conditions.push(String::from("sortOrder = :sortOrder"));
+    }
+
+    if conditions.is_empty() {
+        return Err(AppError::Validation(String::from(
+            "At least one smart list field must be updated",
+        )));
+    }
+
+    let mut sql = vec![String::from("update smartLists set")];
+    sql.push(conditions.join(", "));
+    sql.push(String::from("where id = :id"));
+
+    let statement = connection.prepare(&sql.join(" "))?;
+
+    statement.bind_by_name(":id", &*id)?;
+
+    for (key, value) in bind_params {
+        statement.bind_by_name(format!(":{}", &key).as_str(), &*value)?;
+    }
+
+    if let Some(sort_order) = sort_order {
+        statement.bind_by_name(":sortOrder", sort_order)?;
+    }
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Smart list {} does not exist", id))?;
+
+    Ok(())
+}
+
+pub fn delete(connection: &mut Connection, id: String) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        delete from smartLists
+        where id = ?
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Smart list {} does not exist", id))?;
+
+    Ok(())
+}
@@ -1,37 +1,37 @@
-use crate::database;
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::timestamp;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlite::State;
-use tauri::AppHandle;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
 pub struct Tag {
     pub id: String,
     pub color: String,
     pub name: String,
 }
 
-pub fn get_all(app_handle: &AppHandle) -> Result<Vec<Tag>, sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-
+pub fn get_all(connection: &mut Connection) -> Result<Vec<Tag>, AppError> {
     let mut tags = Vec::new();
 
-    let mut statement = connection
-        .prepare(
-            "
+    let statement = connection.prepare(
+        "
         select
           id,
           color,
           name
         from tags
+        where deletedAt is null
       ",
-        )
-        .unwrap();
+    )?;
 
-    while let State::Row = statement.next().unwrap() {
+    while let State::Row = statement.next()? {
         let tag = Tag {
-            id: statement.read::<String>(0).unwrap(),
-            color: statement.read::<String>(1).unwrap(),
-            name: statement.read::<String>(2).unwrap(),
+            id: statement.read::<String>(0)?,
+            color: statement.read::<String>(1)?,
+            name: statement.read::<String>(2)?,
         };
 
         tags.push(tag)
@@ -40,12 +40,9 @@ pub fn get_all(app_handle: &AppHandle) -> Result<Vec<Tag>, sqlite::Error> {
     Ok(tags)
 }
 
-pub fn get(app_handle: &AppHandle, id: String) -> Result<Tag, sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-
-    let mut statement = connection
-        .prepare(
-            "
+pub fn get(connection: &mut Connection, id: String) -> Result<Tag, AppError> {
+    let statement = connection.prepare(
+        "
         select
           id,
           color,
@@ -53,37 +50,31 @@ pub fn get(app_handle: &AppHandle, id: String) -> Result<Tag, sqlite::Error> {
         from tags
         where
             id = ?
+            and deletedAt is null
       ",
-        )
-        .unwrap();
+    )?;
 
-    statement.bind(1, &*id).unwrap();
+    statement.bind(1, &*id)?;
 
-    while let Ok(State::Row) = statement.next() {
+    if let State::Row = statement.next()? {
         return Ok(Tag {
-            id: statement.read::<String>(0).unwrap(),
-            color: statement.read::<String>(1).unwrap(),
-            name: statement.read::<String>(2).unwrap(),
+            id: statement.read::<String>(0)?,
+            color: statement.read::<String>(1)?,
+            name: statement.read::<String>(2)?,
         });
     }
 
-    Err(sqlite::Error {
-        code: Some(0001),
-        message: Some("Tag not found".to_string()),
-    })
+    Err(AppError::NotFound(format!("Tag {} does not exist", id)))
 }
 
 pub fn create(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     id: String,
     name: String,
     color: String,
-) -> Result<Tag, sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-
-    let mut statement = connection
-        .prepare(
-            "
+) -> Result<Tag, AppError> {
+    let statement = connection.prepare(
+        "
         insert into tags (
           id,
           name,
@@ -94,21 +85,13 @@ pub fn create(
           ?
         )
       ",
-        )
-        .unwrap();
-
-    println!("{}", id);
-
-    statement.bind(1, &*id).unwrap();
-    statement.bind(2, &*name).unwrap();
-    statement.bind(3, &*color).unwrap();
+    )?;
 
-    statement.next().unwrap();
+    statement.bind(1, &*id)?;
+    statement.bind(2, &*name)?;
+    statement.bind(3, &*color)?;
 
-    println!("Creating tag");
-    println!("  - id: {}", id);
-    println!("  - name: {}", name);
-    println!("  - color: {}", color);
+    statement.next()?;
 
     let tag = Tag { id, name, color };
 
@@ -116,11 +99,11 @@ pub fn create(
 }
 
 pub fn update(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     id: String,
     name: Option<String>,
     color: Option<String>,
-) -> Result<(), sqlite::Error> {
+) -> Result<(), AppError> {
     let mut conditions: Vec<String> = Vec::new();
 
     let parameter_mapping = vec![
@@ -137,56 +120,118 @@ pub fn update(
     let mut bind_params: Vec<(String, String)> = Vec::new();
 
     for (key, value) in parameter_mapping {
-        if !value.is_none() {
+        if let Some(value) = value {
             conditions.push(format!("{} = :{}", key, key));
-            bind_params.push((key, value.unwrap()));
+            bind_params.push((key, value));
         }
     }
 
-    let mut sql = vec![String::from("update tags set")];
-    sql.push(conditions.join(" "));
-    sql.push(String::from("where id = :id"));
+    if conditions.is_empty() {
+        return Err(AppError::Validation(String::from(
+            "At least one tag field must be updated",
+        )));
+    }
 
-    let connection = database::initialize_database(app_handle)?;
+    let mut sql = vec![String::from("update tags set")];
+    sql.push(conditions.join(", "));
+    sql.push(String::from("where id = :id and deletedAt is null"));
 
-    let mut statement = connection.prepare(sql.join(" ")).unwrap();
+    let statement = connection.prepare(&sql.join(" "))?;
 
-    statement.bind_by_name(":id", &*id).unwrap();
+    statement.bind_by_name(":id", &*id)?;
 
     for (key, value) in bind_params {
-        statement
-            .bind_by_name(format!(":{}", &key).as_str(), &*value)
-            .unwrap();
+        statement.bind_by_name(format!(":{}", &key).as_str(), &*value)?;
     }
 
-    statement.next().unwrap();
+    statement.next()?;
 
-    println!("Updating tag");
-    println!("  - id: {}", id);
-    println!("  - name: {}", name.unwrap_or(String::from("<none>")));
-    println!("  - color: {}", color.unwrap_or(String::from("<none>")));
+    connection.expect_changes(&format!("Tag {} does not exist", id))?;
 
     Ok(())
 }
 
-pub fn delete(app_handle: &AppHandle, id: String) -> Result<(), sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-
-    let mut statement = connection
-        .prepare(
-            "
-        delete from tags
-        where id = ?
+/// Moves the tag to the trash. Items keep it, but it's hidden from them until
+/// it's restored.
+pub fn delete(
+    connection: &mut Connection,
+    id: String,
+    deleted_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        update tags
+        set deletedAt = ?
+        where
+          id = ?
+          and deletedAt is null
       ",
-        )
-        .unwrap();
+    )?;
 
-    statement.bind(1, &*id).unwrap();
+    statement.bind(1, &*timestamp::to_sql(&deleted_at))?;
+    statement.bind(2, &*id)?;
 
-    statement.next().unwrap();
+    statement.next()?;
 
-    println!("Deleting tag");
-    println!("  - id: {}", id);
+    connection.expect_changes(&format!("Tag {} does not exist", id))?;
 
     Ok(())
 }
+
+pub fn restore(connection: &mut Connection, id: String) -> Result<Tag, AppError> {
+    let statement = connection.prepare(
+        "
+        update tags
+        set deletedAt = null
+        where
+          id = ?
+          and deletedAt is not null
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Tag {} is not in the trash", id))?;
+
+    return get(connection, id);
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedTag {
+    pub tag: Tag,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// The tags in the trash, most recently deleted first.
+pub fn get_trashed(connection: &mut Connection) -> Result<Vec<TrashedTag>, AppError> {
+    let mut tags = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          color,
+          name,
+          deletedAt
+        from tags
+        where deletedAt is not null
+        order by deletedAt desc, id
+      ",
+    )?;
+
+    while let State::Row = statement.next()? {
+        tags.push(TrashedTag {
+            tag: Tag {
+                id: statement.read::<String>(0)?,
+                color: statement.read::<String>(1)?,
+                name: statement.read::<String>(2)?,
+            },
+            deleted_at: timestamp::from_sql(&statement.read::<String>(3)?)?,
+        });
+    }
+
+    Ok(tags)
+}
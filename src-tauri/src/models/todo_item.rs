@@ -1,134 +1,628 @@
-use crate::database;
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::filter;
+use crate::models::project;
 use crate::models::tag;
+use crate::rank;
+use crate::recurrence::{RecurrenceMode, Rule};
+use crate::search;
+use crate::timestamp;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use sqlite::State;
-use tauri::AppHandle;
+use sqlite::{State, Statement};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub fn to_sql(self) -> i64 {
+        match self {
+            Priority::None => 0,
+            Priority::Low => 1,
+            Priority::Medium => 2,
+            Priority::High => 3,
+            Priority::Urgent => 4,
+        }
+    }
+
+    pub fn from_sql(value: i64) -> Result<Priority, AppError> {
+        match value {
+            0 => Ok(Priority::None),
+            1 => Ok(Priority::Low),
+            2 => Ok(Priority::Medium),
+            3 => Ok(Priority::High),
+            4 => Ok(Priority::Urgent),
+            _ => Err(AppError::Database(format!(
+                "Invalid priority {} in database",
+                value
+            ))),
+        }
+    }
+}
+
+/// How a list of todo items is sorted. Every order ends with the manual order
+/// and then the id, so items that compare equal always come back the same way.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    Manual,
+    Priority,
+    DueDate,
+    Created,
+    Title,
+    CompletedAt,
+}
+
+impl SortOrder {
+    fn to_sql(self) -> &'static str {
+        match self {
+            SortOrder::Manual => "position, id",
+            SortOrder::Priority => "priority desc, position, id",
+            SortOrder::DueDate => {
+                "dueDate is null, dueDate, dueTime is null, dueTime, position, id"
+            }
+            SortOrder::Created => "createdAt, position, id",
+            SortOrder::Title => "title collate nocase, position, id",
+            SortOrder::CompletedAt => "completedAt is null, completedAt, position, id",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
 pub struct TodoItem {
     pub id: String,
     pub title: String,
     pub description: Option<String>,
     pub notes: Option<String>,
     pub is_completed: bool,
-    pub date_completed: Option<String>,
-    pub time_completed: Option<String>,
-    pub timezone_completed: Option<String>,
-    pub date_created: String,
-    pub time_created: String,
-    pub timezone_created: String,
+    pub created_at: DateTime<Utc>,
+    pub created_timezone: String,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub completed_timezone: Option<String>,
+    pub due_date: Option<NaiveDate>,
+    pub due_time: Option<NaiveTime>,
+    pub start_date: Option<NaiveDate>,
+    pub recurrence: Option<String>,
+    pub recurrence_mode: Option<RecurrenceMode>,
+    pub series_id: Option<String>,
+    pub occurrence: Option<u32>,
+    pub parent_id: Option<String>,
+    pub completes_with_children: bool,
+    pub child_count: u32,
+    pub completed_child_count: u32,
+    pub project_id: Option<String>,
+    pub position: String,
+    pub priority: Priority,
+    /// The item's tags, sorted by name.
+    pub tags: Vec<tag::Tag>,
 }
 
-pub fn get_all(
-    app_handle: &AppHandle,
-    date_completed: String,
-) -> Result<Vec<TodoItem>, sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-
-    let mut todo_items = Vec::new();
-
-    let mut statement = connection
-        .prepare(
-            "
-        select
+const COLUMNS: &str = "
           id,
           title,
           description,
           notes,
           isCompleted,
-          dateCompleted,
-          timeCompleted,
-          timezoneCompleted,
-          dateCreated,
-          timeCreated,
-          timezoneCreated
+          createdAt,
+          createdTimezone,
+          completedAt,
+          completedTimezone,
+          dueDate,
+          dueTime,
+          startDate,
+          recurrence,
+          recurrenceMode,
+          seriesId,
+          occurrence,
+          parentId,
+          completesWithChildren,
+          (
+            select count(*)
+            from todoItems as children
+            where
+              children.parentId = todoItems.id
+              and children.deletedAt is null
+          ),
+          (
+            select count(*)
+            from todoItems as children
+            where
+              children.parentId = todoItems.id
+              and children.isCompleted = 'true'
+              and children.deletedAt is null
+          ),
+          projectId,
+          position,
+          priority,
+          (
+            select json_group_array(json_object('id', id, 'color', color, 'name', name))
+            from (
+              select
+                tags.id,
+                tags.color,
+                tags.name
+              from todoItemsTags
+              join tags on tags.id = todoItemsTags.tagId
+              where
+                todoItemsTags.todoItemId = todoItems.id
+                and tags.deletedAt is null
+              order by tags.name collate nocase
+            )
+          )
+";
+
+/// Narrows a search down. Every filter that is set must match.
+#[derive(Serialize, Deserialize, Debug, Default, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilters {
+    pub is_completed: Option<bool>,
+    pub project_id: Option<String>,
+    pub tag_id: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub todo_item: TodoItem,
+    /// The title with the matching words marked.
+    pub title: Vec<search::TextPart>,
+    /// The part of the title, description or notes that matches best.
+    pub snippet: Vec<search::TextPart>,
+    /// BM25 relevance, where lower is more relevant.
+    pub rank: f64,
+}
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+// Items in archived projects are hidden from every view but their project's.
+const IN_ACTIVE_PROJECT: &str = "
+          (
+            projectId is null
+            or projectId in (select id from projects where isArchived = 'false')
+          )
+";
+
+fn read(statement: &Statement) -> Result<TodoItem, AppError> {
+    return Ok(TodoItem {
+        id: statement.read::<String>(0)?,
+        title: statement.read::<String>(1)?,
+        description: statement.read::<Option<String>>(2)?,
+        notes: statement.read::<Option<String>>(3)?,
+        is_completed: statement.read::<String>(4)? == "true",
+        created_at: timestamp::from_sql(&statement.read::<String>(5)?)?,
+        created_timezone: statement.read::<String>(6)?,
+        completed_at: statement
+            .read::<Option<String>>(7)?
+            .map(|completed_at| timestamp::from_sql(&completed_at))
+            .transpose()?,
+        completed_timezone: statement.read::<Option<String>>(8)?,
+        due_date: statement
+            .read::<Option<String>>(9)?
+            .map(|due_date| timestamp::date_from_sql(&due_date))
+            .transpose()?,
+        due_time: statement
+            .read::<Option<String>>(10)?
+            .map(|due_time| timestamp::time_from_sql(&due_time))
+            .transpose()?,
+        start_date: statement
+            .read::<Option<String>>(11)?
+            .map(|start_date| timestamp::date_from_sql(&start_date))
+            .transpose()?,
+        recurrence: statement.read::<Option<String>>(12)?,
+        recurrence_mode: statement
+            .read::<Option<String>>(13)?
+            .map(|recurrence_mode| RecurrenceMode::from_sql(&recurrence_mode))
+            .transpose()?,
+        series_id: statement.read::<Option<String>>(14)?,
+        occurrence: statement
+            .read::<Option<i64>>(15)?
+            .map(|occurrence| occurrence as u32),
+        parent_id: statement.read::<Option<String>>(16)?,
+        completes_with_children: statement.read::<String>(17)? == "true",
+        child_count: statement.read::<i64>(18)? as u32,
+        completed_child_count: statement.read::<i64>(19)? as u32,
+        project_id: statement.read::<Option<String>>(20)?,
+        position: statement.read::<String>(21)?,
+        priority: Priority::from_sql(statement.read::<i64>(22)?)?,
+        // Tags come as a JSON array so that loading a list of items takes a
+        // single query.
+        tags: serde_json::from_str(&statement.read::<String>(23)?)
+            .map_err(|error| AppError::Database(format!("Invalid tags in database: {}", error)))?,
+    });
+}
+
+fn select_sql(conditions: &str, order: &str) -> String {
+    return format!(
+        "
+        select {}
         from todoItems
-        where
-          dateCompleted = ? or dateCompleted is null
+        where {}
+        order by {}
       ",
-        )
-        .unwrap();
-
-    statement.bind(1, &*date_completed).unwrap();
-
-    while let State::Row = statement.next().unwrap() {
-        let todo_item = TodoItem {
-            id: statement.read::<String>(0).unwrap(),
-            title: statement.read::<String>(1).unwrap(),
-            description: statement
-                .read::<Option<String>>(2)
-                .unwrap_or_else(|_error| -> Option<String> { None }),
-            notes: statement
-                .read::<Option<String>>(3)
-                .unwrap_or_else(|_error| -> Option<String> { None }),
-            is_completed: statement.read::<String>(4).unwrap() == "true",
-            date_completed: statement
-                .read::<Option<String>>(5)
-                .unwrap_or_else(|_error| -> Option<String> { None }),
-            time_completed: statement
-                .read::<Option<String>>(6)
-                .unwrap_or_else(|_error| -> Option<String> { None }),
-            timezone_completed: statement
-                .read::<Option<String>>(7)
-                .unwrap_or_else(|_error| -> Option<String> { None }),
-            date_created: statement.read::<String>(8).unwrap(),
-            time_created: statement.read::<String>(9).unwrap(),
-            timezone_created: statement.read::<String>(10).unwrap(),
-        };
-
-        todo_items.push(todo_item)
+        COLUMNS, conditions, order
+    );
+}
+
+fn read_all(
+    statement: &mut Statement,
+    parameters: &[(&str, &str)],
+) -> Result<Vec<TodoItem>, AppError> {
+    let mut todo_items = Vec::new();
+
+    for (name, value) in parameters {
+        statement.bind_by_name(name, *value)?;
+    }
+
+    while let State::Row = statement.next()? {
+        todo_items.push(read(statement)?)
     }
 
     return Ok(todo_items);
 }
 
+fn select(
+    connection: &mut Connection,
+    conditions: &str,
+    order: &str,
+    parameters: &[(&str, &str)],
+) -> Result<Vec<TodoItem>, AppError> {
+    let statement = connection.prepare(&select_sql(conditions, order))?;
+
+    return read_all(statement, parameters);
+}
+
+/// Like `select`, leaving out items in the trash.
+fn query(
+    connection: &mut Connection,
+    conditions: &str,
+    order: &str,
+    parameters: &[(&str, &str)],
+) -> Result<Vec<TodoItem>, AppError> {
+    return select(
+        connection,
+        &format!("deletedAt is null and ({})", conditions),
+        order,
+        parameters,
+    );
+}
+
+pub fn get(connection: &mut Connection, id: &str) -> Result<TodoItem, AppError> {
+    return query(connection, "id = :id", "id", &[(":id", id)])?
+        .pop()
+        .ok_or_else(|| AppError::NotFound(format!("Todo item {} does not exist", id)));
+}
+
+/// The subtasks directly under the item, in order.
+pub fn get_children(connection: &mut Connection, id: String) -> Result<Vec<TodoItem>, AppError> {
+    get(connection, &id)?;
+
+    return query(
+        connection,
+        "parentId = :id",
+        "position, id",
+        &[(":id", &id)],
+    );
+}
+
+/// Searches the title, description and notes of every item, completed or not,
+/// for `query` (see `search::to_fts_query`). Title matches rank highest, then
+/// description matches, then notes.
+pub fn search(
+    connection: &mut Connection,
+    query: &str,
+    filters: SearchFilters,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut results = Vec::new();
+
+    let fts_query = match search::to_fts_query(query)? {
+        Some(fts_query) => fts_query,
+        None => return Ok(results),
+    };
+
+    let mut conditions = vec![String::from("deletedAt is null")];
+    let mut parameters: Vec<(&str, String)> = vec![(":query", fts_query)];
+
+    if let Some(is_completed) = filters.is_completed {
+        conditions.push(String::from("isCompleted = :isCompleted"));
+        parameters.push((":isCompleted", is_completed.to_string()));
+    }
+
+    if let Some(project_id) = filters.project_id {
+        conditions.push(String::from("projectId = :projectId"));
+        parameters.push((":projectId", project_id));
+    }
+
+    if let Some(tag_id) = filters.tag_id {
+        conditions.push(String::from(
+            "id in (select todoItemId from todoItemsTags where tagId = :tagId)",
+        ));
+        parameters.push((":tagId", tag_id));
+    }
+
+    let statement = connection.prepare(&format!(
+        "
+        select
+          {},
+          matches.titleHighlight,
+          matches.snippet,
+          matches.score
+        from todoItems
+        join (
+          select
+            id as todoItemId,
+            highlight(todoItemsSearch, 1, char(2), char(3)) as titleHighlight,
+            snippet(todoItemsSearch, -1, char(2), char(3), '…', 16) as snippet,
+            bm25(todoItemsSearch, 0.0, 10.0, 5.0, 1.0) as score
+          from todoItemsSearch
+          where todoItemsSearch match :query
+        ) as matches on matches.todoItemId = todoItems.id
+        where {}
+        order by matches.score, position, id
+        limit :limit
+      ",
+        COLUMNS,
+        conditions.join(" and ")
+    ))?;
+
+    for (name, value) in &parameters {
+        statement.bind_by_name(name, &**value)?;
+    }
+
+    statement.bind_by_name(
+        ":limit",
+        i64::from(filters.limit.unwrap_or(DEFAULT_SEARCH_LIMIT)),
+    )?;
+
+    // The search columns come after the todo item's own.
+    let title_index = statement.column_count() - 3;
+
+    while let State::Row = statement.next()? {
+        results.push(SearchResult {
+            todo_item: read(statement)?,
+            title: search::split_matches(&statement.read::<String>(title_index)?),
+            snippet: search::split_matches(&statement.read::<String>(title_index + 1)?),
+            rank: statement.read::<f64>(title_index + 2)?,
+        });
+    }
+
+    return Ok(results);
+}
+
+/// Returns the todo items to show for `date` in `timezone`: whatever was
+/// completed during that local day, plus everything that is still incomplete
+/// and has started. Overdue items are incomplete, so they carry over into
+/// each day's list until they're done. With a `project_id`, only that
+/// project's items are returned, even if it's archived. Incomplete items come
+/// first, each group sorted by `sort`.
+pub fn get_all(
+    connection: &mut Connection,
+    date: NaiveDate,
+    timezone: Tz,
+    project_id: Option<String>,
+    sort: SortOrder,
+) -> Result<Vec<TodoItem>, AppError> {
+    let (start, end) = timestamp::day_bounds(date, timezone);
+    let start = timestamp::to_sql(&start);
+    let end = timestamp::to_sql(&end);
+    let date = timestamp::date_to_sql(&date);
+
+    let mut parameters = vec![(":start", &*start), (":end", &*end), (":date", &*date)];
+
+    let scope = match &project_id {
+        Some(project_id) => {
+            parameters.push((":projectId", project_id.as_str()));
+            "projectId = :projectId"
+        }
+        None => IN_ACTIVE_PROJECT,
+    };
+
+    return query(
+        connection,
+        &format!(
+            "
+          (
+            (completedAt >= :start and completedAt < :end)
+            or (
+              completedAt is null
+              and (startDate is null or startDate <= :date)
+            )
+          )
+          and {}
+        ",
+            scope
+        ),
+        &format!("completedAt is not null, {}", sort.to_sql()),
+        &parameters,
+    );
+}
+
+/// Items matching a compiled filter, incomplete ones first, each group sorted
+/// by `sort`. Items in archived projects only match filters that pick items
+/// by project.
+pub fn get_filtered(
+    connection: &mut Connection,
+    filter: &filter::CompiledFilter,
+    sort: SortOrder,
+) -> Result<Vec<TodoItem>, AppError> {
+    let parameters: Vec<(&str, &str)> = filter
+        .parameters
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    let scope = if filter.mentions_project {
+        "1"
+    } else {
+        IN_ACTIVE_PROJECT
+    };
+
+    // Each filter is different SQL, and previews compile one per keystroke.
+    let mut statement = connection.prepare_uncached(&select_sql(
+        &format!("deletedAt is null and ({}) and {}", filter.sql, scope),
+        &format!("completedAt is not null, {}", sort.to_sql()),
+    ))?;
+
+    return read_all(&mut statement, &parameters);
+}
+
+/// Incomplete items due on `date` that have started by then. Items due on the
+/// same day are listed by time, then in their manual order.
+pub fn get_due_on(connection: &mut Connection, date: NaiveDate) -> Result<Vec<TodoItem>, AppError> {
+    return query(
+        connection,
+        &format!(
+            "
+          isCompleted = 'false'
+          and dueDate = :date
+          and (startDate is null or startDate <= :date)
+          and {}
+        ",
+            IN_ACTIVE_PROJECT
+        ),
+        "dueTime is null, dueTime, position, id",
+        &[(":date", &timestamp::date_to_sql(&date))],
+    );
+}
+
+/// Incomplete items that were due before `date`, oldest first.
+pub fn get_overdue(
+    connection: &mut Connection,
+    date: NaiveDate,
+) -> Result<Vec<TodoItem>, AppError> {
+    return query(
+        connection,
+        &format!(
+            "
+          isCompleted = 'false'
+          and dueDate < :date
+          and (startDate is null or startDate <= :date)
+          and {}
+        ",
+            IN_ACTIVE_PROJECT
+        ),
+        "dueDate, dueTime is null, dueTime, position, id",
+        &[(":date", &timestamp::date_to_sql(&date))],
+    );
+}
+
+/// Incomplete items due in the `days` days after `date`.
+pub fn get_upcoming(
+    connection: &mut Connection,
+    date: NaiveDate,
+    days: u32,
+) -> Result<Vec<TodoItem>, AppError> {
+    let last_date = date + Duration::days(i64::from(days));
+
+    return query(
+        connection,
+        &format!(
+            "
+          isCompleted = 'false'
+          and dueDate > :date
+          and dueDate <= :lastDate
+          and {}
+        ",
+            IN_ACTIVE_PROJECT
+        ),
+        "dueDate, dueTime is null, dueTime, position, id",
+        &[
+            (":date", &timestamp::date_to_sql(&date)),
+            (":lastDate", &timestamp::date_to_sql(&last_date)),
+        ],
+    );
+}
+
+/// Incomplete items without a due date that have started by `date`, in order.
+pub fn get_undated(
+    connection: &mut Connection,
+    date: NaiveDate,
+) -> Result<Vec<TodoItem>, AppError> {
+    return query(
+        connection,
+        &format!(
+            "
+          isCompleted = 'false'
+          and dueDate is null
+          and (startDate is null or startDate <= :date)
+          and {}
+        ",
+            IN_ACTIVE_PROJECT
+        ),
+        "position, id",
+        &[(":date", &timestamp::date_to_sql(&date))],
+    );
+}
+
+/// Creates an item in `project_id`. Subtasks always belong to their parent's
+/// project.
 pub fn create(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     id: String,
     title: String,
-    date_created: String,
-    time_created: String,
-    timezone_created: String,
-) -> Result<TodoItem, sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection
-        .prepare(
-            "
+    created_at: DateTime<Utc>,
+    timezone: Tz,
+    parent_id: Option<String>,
+    project_id: Option<String>,
+) -> Result<TodoItem, AppError> {
+    let project_id = match &parent_id {
+        Some(parent_id) => get(connection, parent_id)?.project_id,
+        None => project_id,
+    };
+
+    if let Some(project_id) = &project_id {
+        project::get(connection, project_id.clone())?;
+    }
+
+    let last_position = get_last_position(connection)?;
+    let position = rank::between(last_position.as_deref(), None)?;
+
+    let statement = connection.prepare(
+        "
         insert into todoItems (
           id,
           title,
           isCompleted,
-          dateCreated,
-          timeCreated,
-          timezoneCreated
+          createdAt,
+          createdTimezone,
+          parentId,
+          projectId,
+          position
         ) values (
           ?,
           ?,
           'false',
           ?,
           ?,
+          ?,
+          ?,
           ?
         )
       ",
-        )
-        .unwrap();
+    )?;
 
-    println!("{}", id);
+    statement.bind(1, &*id)?;
+    statement.bind(2, &*title)?;
+    statement.bind(3, &*timestamp::to_sql(&created_at))?;
+    statement.bind(4, timezone.name())?;
+    statement.bind(5, parent_id.as_deref())?;
+    statement.bind(6, project_id.as_deref())?;
+    statement.bind(7, &*position)?;
 
-    statement.bind(1, &*id).unwrap();
-    statement.bind(2, &*title).unwrap();
-    statement.bind(3, &*date_created).unwrap();
-    statement.bind(4, &*time_created).unwrap();
-    statement.bind(5, &*timezone_created).unwrap();
+    statement.next()?;
 
-    statement.next().unwrap();
-
-    println!("Creating todo item");
-    println!("  - title: {}", title);
-    println!("  - date created: {}", date_created);
-    println!("  - time created: {}", time_created);
-    println!("  - timezone created: {}", timezone_created);
+    reopen_ancestors(connection, &id)?;
 
     let todo_item = TodoItem {
         id: id,
@@ -136,88 +630,878 @@ pub fn create(
         description: None,
         notes: None,
         is_completed: false,
-        date_completed: None,
-        time_completed: None,
-        timezone_completed: None,
-        date_created: date_created,
-        time_created: time_created,
-        timezone_created: timezone_created,
+        created_at: created_at,
+        created_timezone: String::from(timezone.name()),
+        completed_at: None,
+        completed_timezone: None,
+        due_date: None,
+        due_time: None,
+        start_date: None,
+        recurrence: None,
+        recurrence_mode: None,
+        series_id: None,
+        occurrence: None,
+        parent_id: parent_id,
+        completes_with_children: false,
+        child_count: 0,
+        completed_child_count: 0,
+        project_id: project_id,
+        position: position,
+        priority: Priority::None,
+        tags: Vec::new(),
     };
 
     Ok(todo_item)
 }
 
+/// Completes the item along with all of its subtasks. Completing an occurrence
+/// of a recurring item creates the next occurrence, which is returned; its
+/// subtasks are completed without creating theirs. Parents that complete with
+/// their children are completed once none of their subtasks are left.
 pub fn complete(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     id: String,
-    date_completed: String,
-    time_completed: String,
-    timezone_completed: String,
-) -> Result<(), sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection
-        .prepare(
-            "
+    completed_at: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<Option<TodoItem>, AppError> {
+    let todo_item = get(connection, &id)?;
+
+    let statement = connection.prepare(
+        "
         update todoItems
         set
           isCompleted = 'true',
-          dateCompleted = ?,
-          timeCompleted = ?,
-          timezoneCompleted = ?
-        where id = ?
+          completedAt = ?,
+          completedTimezone = ?
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, &*timestamp::to_sql(&completed_at))?;
+    statement.bind(2, timezone.name())?;
+    statement.bind(3, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    complete_descendants(connection, &id, completed_at, timezone)?;
+
+    let completed_on = completed_at.with_timezone(&timezone).date_naive();
+
+    let next = if todo_item.is_completed || has_later_occurrence(connection, &todo_item)? {
+        None
+    } else {
+        match next_occurrence(&todo_item, completed_on)? {
+            Some((reference, next_date)) => Some(create_occurrence(
+                connection,
+                &todo_item,
+                reference,
+                next_date,
+                completed_at,
+                timezone,
+            )?),
+            None => None,
+        }
+    };
+
+    complete_finished_ancestors(connection, todo_item.parent_id, completed_at, timezone)?;
+
+    return Ok(next);
+}
+
+fn complete_descendants(
+    connection: &mut Connection,
+    id: &str,
+    completed_at: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        with recursive descendants(id) as (
+          select id from todoItems where parentId = :id
+          union
+          select todoItems.id
+          from todoItems
+          join descendants on todoItems.parentId = descendants.id
+        )
+        update todoItems
+        set
+          isCompleted = 'true',
+          completedAt = :completedAt,
+          completedTimezone = :timezone
+        where
+          isCompleted = 'false'
+          and deletedAt is null
+          and id in (select id from descendants)
+      ",
+    )?;
+
+    statement.bind_by_name(":id", id)?;
+    statement.bind_by_name(":completedAt", &*timestamp::to_sql(&completed_at))?;
+    statement.bind_by_name(":timezone", timezone.name())?;
+
+    statement.next()?;
+
+    return Ok(());
+}
+
+fn complete_finished_ancestors(
+    connection: &mut Connection,
+    parent_id: Option<String>,
+    completed_at: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<(), AppError> {
+    let mut parent_id = parent_id;
+
+    while let Some(id) = parent_id {
+        let parent = get(connection, &id)?;
+
+        if parent.is_completed
+            || !parent.completes_with_children
+            || parent.child_count == 0
+            || parent.completed_child_count < parent.child_count
+        {
+            break;
+        }
+
+        let statement = connection.prepare(
+            "
+            update todoItems
+            set
+              isCompleted = 'true',
+              completedAt = ?,
+              completedTimezone = ?
+            where
+              id = ?
+              and deletedAt is null
+          ",
+        )?;
+
+        statement.bind(1, &*timestamp::to_sql(&completed_at))?;
+        statement.bind(2, timezone.name())?;
+        statement.bind(3, &*id)?;
+
+        statement.next()?;
+
+        parent_id = parent.parent_id;
+    }
+
+    return Ok(());
+}
+
+// A completed item can't have unfinished subtasks, so anything that leaves an
+// incomplete item under a completed one reopens the completed ones above it.
+fn reopen_ancestors(connection: &mut Connection, id: &str) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        with recursive ancestors(id) as (
+          select parentId from todoItems where id = :id and isCompleted = 'false'
+          union
+          select todoItems.parentId
+          from todoItems
+          join ancestors on todoItems.id = ancestors.id
+        )
+        update todoItems
+        set
+          isCompleted = 'false',
+          completedAt = null,
+          completedTimezone = null
+        where
+          isCompleted = 'true'
+          and id in (select id from ancestors)
+      ",
+    )?;
+
+    statement.bind_by_name(":id", id)?;
+
+    statement.next()?;
+
+    return Ok(());
+}
+
+/// The date the next occurrence is counted from, and the date it falls on,
+/// for an occurrence that was completed or skipped on `date`. Returns `None`
+/// when the item doesn't recur or its series has ended.
+fn next_occurrence(
+    todo_item: &TodoItem,
+    date: NaiveDate,
+) -> Result<Option<(NaiveDate, NaiveDate)>, AppError> {
+    let rule = match &todo_item.recurrence {
+        Some(recurrence) => Rule::parse(recurrence)?,
+        None => return Ok(None),
+    };
+
+    let occurrence = todo_item.occurrence.unwrap_or(1);
+
+    if rule.count.map_or(false, |count| occurrence >= count) {
+        return Ok(None);
+    }
+
+    let reference = match todo_item.recurrence_mode {
+        Some(RecurrenceMode::FromCompletion) => date,
+        _ => todo_item.due_date.unwrap_or(date),
+    };
+
+    return Ok(rule
+        .next_after(reference, reference)
+        .map(|next_date| (reference, next_date)));
+}
+
+// Uncompleting and completing an occurrence again mustn't create its
+// successor twice.
+fn has_later_occurrence(
+    connection: &mut Connection,
+    todo_item: &TodoItem,
+) -> Result<bool, AppError> {
+    let series_id = match &todo_item.series_id {
+        Some(series_id) => series_id,
+        None => return Ok(false),
+    };
+
+    let statement = connection.prepare(
+        "
+        select count(*)
+        from todoItems
+        where
+          seriesId = ?
+          and occurrence > ?
+          and deletedAt is null
       ",
+    )?;
+
+    statement.bind(1, &**series_id)?;
+    statement.bind(2, i64::from(todo_item.occurrence.unwrap_or(1)))?;
+
+    statement.next()?;
+
+    return Ok(statement.read::<i64>(0)? > 0);
+}
+
+fn create_occurrence(
+    connection: &mut Connection,
+    previous: &TodoItem,
+    reference: NaiveDate,
+    due_date: NaiveDate,
+    created_at: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<TodoItem, AppError> {
+    let id = Uuid::new_v4().to_string();
+    let series_id = previous
+        .series_id
+        .clone()
+        .unwrap_or_else(|| previous.id.clone());
+    let occurrence = previous.occurrence.unwrap_or(1) + 1;
+    // The start date keeps its distance from the due date.
+    let start_date = previous
+        .start_date
+        .map(|start_date| start_date + (due_date - reference));
+    // The next occurrence takes the place of the one that was completed.
+    let next_position = get_next_position(connection, &previous.position)?;
+    let position = rank::between(Some(&previous.position), next_position.as_deref())?;
+
+    let statement = connection.prepare(
+        "
+        insert into todoItems (
+          id,
+          title,
+          description,
+          notes,
+          isCompleted,
+          createdAt,
+          createdTimezone,
+          dueDate,
+          dueTime,
+          startDate,
+          recurrence,
+          recurrenceMode,
+          seriesId,
+          occurrence,
+          parentId,
+          completesWithChildren,
+          projectId,
+          position,
+          priority
+        ) values (
+          ?,
+          ?,
+          ?,
+          ?,
+          'false',
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?,
+          ?
         )
-        .unwrap();
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+    statement.bind(2, &*previous.title)?;
+    statement.bind(3, previous.description.as_deref())?;
+    statement.bind(4, previous.notes.as_deref())?;
+    statement.bind(5, &*timestamp::to_sql(&created_at))?;
+    statement.bind(6, timezone.name())?;
+    statement.bind(7, &*timestamp::date_to_sql(&due_date))?;
+    statement.bind(
+        8,
+        previous
+            .due_time
+            .map(|due_time| timestamp::time_to_sql(&due_time))
+            .as_deref(),
+    )?;
+    statement.bind(
+        9,
+        start_date
+            .map(|start_date| timestamp::date_to_sql(&start_date))
+            .as_deref(),
+    )?;
+    statement.bind(10, previous.recurrence.as_deref())?;
+    statement.bind(
+        11,
+        previous
+            .recurrence_mode
+            .map(|recurrence_mode| recurrence_mode.to_sql()),
+    )?;
+    statement.bind(12, &*series_id)?;
+    statement.bind(13, i64::from(occurrence))?;
+    statement.bind(14, previous.parent_id.as_deref())?;
+    statement.bind(15, previous.completes_with_children.to_string().as_str())?;
+    statement.bind(16, previous.project_id.as_deref())?;
+    statement.bind(17, &*position)?;
+    statement.bind(18, previous.priority.to_sql())?;
+
+    statement.next()?;
+
+    for tag in get_tags(connection, previous.id.clone())? {
+        add_tag(connection, id.clone(), tag.id)?;
+    }
 
-    statement.bind(1, &*date_completed).unwrap();
-    statement.bind(2, &*time_completed).unwrap();
-    statement.bind(3, &*timezone_completed).unwrap();
-    statement.bind(4, &*id).unwrap();
+    return get(connection, &id);
+}
+
+/// Makes the item recur. An item that isn't part of a series yet starts one
+/// as its first occurrence.
+pub fn set_recurrence(
+    connection: &mut Connection,
+    id: String,
+    rule: &Rule,
+    mode: RecurrenceMode,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set
+          recurrence = ?,
+          recurrenceMode = ?,
+          seriesId = coalesce(seriesId, id),
+          occurrence = coalesce(occurrence, 1)
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
 
-    statement.next().unwrap();
+    statement.bind(1, &*rule.to_string())?;
+    statement.bind(2, mode.to_sql())?;
+    statement.bind(3, &*id)?;
 
-    println!("Completing todo item");
-    println!("  - id: {}", id);
-    println!("  - date completed: {}", date_completed);
-    println!("  - time completed: {}", time_completed);
-    println!("  - timezone completed: {}", timezone_completed);
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
 
     Ok(())
 }
 
-pub fn uncomplete(app_handle: &AppHandle, id: String) -> Result<(), sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection
-        .prepare(
-            "
+/// Moves an occurrence of a recurring item to the occurrence after it, as if
+/// it had been completed on `date` without being done. If that occurrence
+/// already exists, because this one was completed before, this one goes to
+/// the trash at `now` in `timezone` instead, and the existing one is returned.
+pub fn skip_occurrence(
+    connection: &mut Connection,
+    id: String,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<TodoItem, AppError> {
+    let todo_item = get(connection, &id)?;
+
+    if todo_item.recurrence.is_none() {
+        return Err(AppError::Validation(format!(
+            "Todo item {} does not recur",
+            id
+        )));
+    }
+
+    if has_later_occurrence(connection, &todo_item)? {
+        delete(connection, id.clone(), now, timezone)?;
+
+        let series_id = todo_item.series_id.unwrap_or_default();
+        let occurrence = todo_item.occurrence.unwrap_or(1).to_string();
+
+        return query(
+            connection,
+            "seriesId = :seriesId and occurrence > :occurrence",
+            "occurrence",
+            &[(":seriesId", &series_id), (":occurrence", &occurrence)],
+        )?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound(format!("Todo item {} has no later occurrence", id)));
+    }
+
+    let (reference, due_date) = next_occurrence(&todo_item, date)?.ok_or_else(|| {
+        AppError::Conflict(format!(
+            "Todo item {} is the last occurrence of its series",
+            id
+        ))
+    })?;
+    let start_date = todo_item
+        .start_date
+        .map(|start_date| start_date + (due_date - reference));
+
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set
+          dueDate = ?,
+          startDate = ?,
+          occurrence = occurrence + 1
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, &*timestamp::date_to_sql(&due_date))?;
+    statement.bind(
+        2,
+        start_date
+            .map(|start_date| timestamp::date_to_sql(&start_date))
+            .as_deref(),
+    )?;
+    statement.bind(3, &*id)?;
+
+    statement.next()?;
+
+    return get(connection, &id);
+}
+
+/// Stops the item's series from recurring. Occurrences that already exist are
+/// kept, but completing them no longer creates new ones.
+pub fn end_recurrence(connection: &mut Connection, id: String) -> Result<(), AppError> {
+    get(connection, &id)?;
+
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set
+          recurrence = null,
+          recurrenceMode = null
+        where
+          id = :id
+          or seriesId = (select seriesId from todoItems where id = :id)
+      ",
+    )?;
+
+    statement.bind_by_name(":id", &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    Ok(())
+}
+
+pub fn uncomplete(connection: &mut Connection, id: String) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
         update todoItems
         set
           isCompleted = 'false',
-          dateCompleted = null,
-          timeCompleted = null,
-          timezoneCompleted = null
-        where id = ?
+          completedAt = null,
+          completedTimezone = null
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    reopen_ancestors(connection, &id)?;
+
+    Ok(())
+}
+
+pub fn set_due_date(
+    connection: &mut Connection,
+    id: String,
+    due_date: Option<NaiveDate>,
+    due_time: Option<NaiveTime>,
+) -> Result<(), AppError> {
+    if due_date.is_none() && due_time.is_some() {
+        return Err(AppError::Validation(String::from(
+            "A due time needs a due date",
+        )));
+    }
+
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set
+          dueDate = ?,
+          dueTime = ?
+        where
+          id = ?
+          and deletedAt is null
       ",
+    )?;
+
+    statement.bind(
+        1,
+        due_date
+            .map(|due_date| timestamp::date_to_sql(&due_date))
+            .as_deref(),
+    )?;
+    statement.bind(
+        2,
+        due_time
+            .map(|due_time| timestamp::time_to_sql(&due_time))
+            .as_deref(),
+    )?;
+    statement.bind(3, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    Ok(())
+}
+
+pub fn set_priority(
+    connection: &mut Connection,
+    id: String,
+    priority: Priority,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set priority = ?
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, priority.to_sql())?;
+    statement.bind(2, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    Ok(())
+}
+
+pub fn set_start_date(
+    connection: &mut Connection,
+    id: String,
+    start_date: Option<NaiveDate>,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set startDate = ?
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(
+        1,
+        start_date
+            .map(|start_date| timestamp::date_to_sql(&start_date))
+            .as_deref(),
+    )?;
+    statement.bind(2, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    Ok(())
+}
+
+pub fn get_last_position(connection: &mut Connection) -> Result<Option<String>, AppError> {
+    let statement = connection.prepare(
+        "
+        select max(position)
+        from todoItems
+      ",
+    )?;
+
+    statement.next()?;
+
+    return Ok(statement.read::<Option<String>>(0)?);
+}
+
+fn get_next_position(
+    connection: &mut Connection,
+    position: &str,
+) -> Result<Option<String>, AppError> {
+    let statement = connection.prepare(
+        "
+        select min(position)
+        from todoItems
+        where position > ?
+      ",
+    )?;
+
+    statement.bind(1, position)?;
+
+    statement.next()?;
+
+    return Ok(statement.read::<Option<String>>(0)?);
+}
+
+/// Moves the item so that it comes right after `before_id` and right before
+/// `after_id`, which have to be next to each other, since only their
+/// positions are looked at. Leaving out `before_id` means nothing comes
+/// before it, so `after_id` has to be the first item, and leaving out
+/// `after_id` moves it to the end, after `before_id`. Every list is ordered by
+/// the same positions, so a move in a project or under a parent keeps its
+/// place in the other lists.
+pub fn move_item(
+    connection: &mut Connection,
+    id: String,
+    before_id: Option<String>,
+    after_id: Option<String>,
+) -> Result<(), AppError> {
+    if before_id.is_none() && after_id.is_none() {
+        return Err(AppError::Validation(String::from(
+            "A todo item has to be moved next to another todo item",
+        )));
+    }
+
+    if before_id.as_ref() == Some(&id) || after_id.as_ref() == Some(&id) {
+        return Err(AppError::Validation(format!(
+            "Todo item {} can't be moved next to itself",
+            id
+        )));
+    }
+
+    let before_position = match &before_id {
+        Some(before_id) => Some(get(connection, before_id)?.position),
+        None => None,
+    };
+    let after_position = match &after_id {
+        Some(after_id) => Some(get(connection, after_id)?.position),
+        None => None,
+    };
+
+    let position = rank::between(before_position.as_deref(), after_position.as_deref())?;
+
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set position = ?
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, &*position)?;
+    statement.bind(2, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    Ok(())
+}
+
+/// Moves the item under `parent_id`, or to the top level when it's `None`,
+/// along with all of its subtasks.
+pub fn set_parent(
+    connection: &mut Connection,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<(), AppError> {
+    let project_id = match &parent_id {
+        Some(parent_id) => Some(get(connection, parent_id)?.project_id),
+        None => None,
+    };
+
+    if let Some(parent_id) = &parent_id {
+        if is_descendant(connection, &id, parent_id)? {
+            return Err(AppError::Validation(format!(
+                "Todo item {} can't be moved under itself or one of its subtasks",
+                id
+            )));
+        }
+    }
+
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set parentId = ?
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, parent_id.as_deref())?;
+    statement.bind(2, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    reopen_ancestors(connection, &id)?;
+
+    if let Some(project_id) = project_id {
+        move_to_project(connection, &id, project_id.as_deref())?;
+    }
+
+    Ok(())
+}
+
+/// Moves the item and its subtasks into `project_id`, or out of any project
+/// when it's `None`. A subtask can only be moved along with its parent.
+pub fn set_project(
+    connection: &mut Connection,
+    id: String,
+    project_id: Option<String>,
+) -> Result<(), AppError> {
+    if get(connection, &id)?.parent_id.is_some() {
+        return Err(AppError::Validation(format!(
+            "Todo item {} is a subtask and belongs to its parent's project",
+            id
+        )));
+    }
+
+    if let Some(project_id) = &project_id {
+        project::get(connection, project_id.clone())?;
+    }
+
+    move_to_project(connection, &id, project_id.as_deref())?;
+
+    Ok(())
+}
+
+fn move_to_project(
+    connection: &mut Connection,
+    id: &str,
+    project_id: Option<&str>,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        with recursive subtree(id) as (
+          select :id
+          union
+          select todoItems.id
+          from todoItems
+          join subtree on todoItems.parentId = subtree.id
         )
-        .unwrap();
+        update todoItems
+        set projectId = :projectId
+        where id in (select id from subtree)
+      ",
+    )?;
 
-    statement.bind(1, &*id).unwrap();
+    statement.bind_by_name(":id", id)?;
+    statement.bind_by_name(":projectId", project_id)?;
 
-    statement.next().unwrap();
+    statement.next()?;
 
-    println!("Uncompleting todo item");
-    println!("  - id: {}", id);
+    return Ok(());
+}
+
+/// Whether `other_id` is `id` or nested anywhere under it.
+fn is_descendant(connection: &mut Connection, id: &str, other_id: &str) -> Result<bool, AppError> {
+    let statement = connection.prepare(
+        "
+        with recursive descendants(id) as (
+          select :id
+          union
+          select todoItems.id
+          from todoItems
+          join descendants on todoItems.parentId = descendants.id
+        )
+        select count(*)
+        from descendants
+        where id = :otherId
+      ",
+    )?;
+
+    statement.bind_by_name(":id", id)?;
+    statement.bind_by_name(":otherId", other_id)?;
+
+    statement.next()?;
+
+    return Ok(statement.read::<i64>(0)? > 0);
+}
+
+/// Turning it on completes the item right away when its subtasks are already
+/// done, as of `completed_at` in `timezone`.
+pub fn set_completes_with_children(
+    connection: &mut Connection,
+    id: String,
+    completes_with_children: bool,
+    completed_at: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
+        update todoItems
+        set completesWithChildren = ?
+        where
+          id = ?
+          and deletedAt is null
+      ",
+    )?;
+
+    statement.bind(1, completes_with_children.to_string().as_str())?;
+    statement.bind(2, &*id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
+
+    if completes_with_children {
+        complete_finished_ancestors(connection, Some(id), completed_at, timezone)?;
+    }
 
     Ok(())
 }
 
 pub fn update(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     id: String,
     title: Option<String>,
     description: Option<String>,
     notes: Option<String>,
-) -> Result<(), sqlite::Error> {
+) -> Result<(), AppError> {
     let mut conditions: Vec<String> = Vec::new();
 
     let parameter_mapping = vec![
@@ -240,72 +1524,177 @@ pub fn update(
     let mut bind_params: Vec<(String, String)> = Vec::new();
 
     for (key, value) in parameter_mapping {
-        if !value.is_none() {
+        if let Some(value) = value {
             conditions.push(format!("{} = :{}", key, key));
-            bind_params.push((key, value.unwrap()));
+            bind_params.push((key, value));
         }
     }
 
+    if conditions.is_empty() {
+        return Err(AppError::Validation(String::from(
+            "At least one todo item field must be updated",
+        )));
+    }
+
     let mut sql = vec![String::from("update todoItems set")];
-    sql.push(conditions.join(" "));
-    sql.push(String::from("where id = :id"));
+    sql.push(conditions.join(", "));
+    sql.push(String::from("where id = :id and deletedAt is null"));
 
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection.prepare(sql.join(" ")).unwrap();
+    let statement = connection.prepare(&sql.join(" "))?;
 
-    statement.bind_by_name(":id", &*id).unwrap();
+    statement.bind_by_name(":id", &*id)?;
 
     for (key, value) in bind_params {
-        statement
-            .bind_by_name(format!(":{}", &key).as_str(), &*value)
-            .unwrap();
+        statement.bind_by_name(format!(":{}", &key).as_str(), &*value)?;
     }
 
-    statement.next().unwrap();
+    statement.next()?;
 
-    println!("Updating todo item");
-    println!("  - id: {}", id);
-    println!("  - title: {}", title.unwrap_or(String::from("<none>")));
-    println!(
-        "  - description: {}",
-        description.unwrap_or(String::from("<none>"))
-    );
-    println!("  - notes: {}", notes.unwrap_or(String::from("<none>")));
+    connection.expect_changes(&format!("Todo item {} does not exist", id))?;
 
     Ok(())
 }
 
-pub fn delete(app_handle: &AppHandle, id: String) -> Result<(), sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection
-        .prepare(
-            "
-        delete from todoItems
-        where id = ?
-      ",
+/// Moves the item and its subtasks to the trash. A parent that completes with
+/// its subtasks is completed in `timezone` if the rest of them are done.
+pub fn delete(
+    connection: &mut Connection,
+    id: String,
+    deleted_at: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<(), AppError> {
+    let todo_item = get(connection, &id)?;
+
+    let statement = connection.prepare(
+        "
+        with recursive subtree(id) as (
+          select :id
+          union
+          select todoItems.id
+          from todoItems
+          join subtree on todoItems.parentId = subtree.id
         )
-        .unwrap();
+        update todoItems
+        set deletedAt = :deletedAt
+        where
+          deletedAt is null
+          and id in (select id from subtree)
+      ",
+    )?;
 
-    statement.bind(1, &*id).unwrap();
+    statement.bind_by_name(":id", &*id)?;
+    statement.bind_by_name(":deletedAt", &*timestamp::to_sql(&deleted_at))?;
 
-    statement.next().unwrap();
+    statement.next()?;
 
-    println!("Deleting todo item");
-    println!("  - id: {}", id);
+    complete_finished_ancestors(connection, todo_item.parent_id, deleted_at, timezone)?;
 
     Ok(())
 }
 
+/// Takes the item out of the trash, along with the subtasks that were trashed
+/// with it. Subtasks that were deleted on their own before stay in the trash.
+pub fn restore(connection: &mut Connection, id: String) -> Result<TodoItem, AppError> {
+    let todo_item = select(
+        connection,
+        "id = :id and deletedAt is not null",
+        "id",
+        &[(":id", &id)],
+    )?
+    .pop()
+    .ok_or_else(|| AppError::NotFound(format!("Todo item {} is not in the trash", id)))?;
+
+    if let Some(parent_id) = &todo_item.parent_id {
+        if get(connection, parent_id).is_err() {
+            return Err(AppError::Conflict(format!(
+                "Todo item {} is a subtask of {}, which is in the trash",
+                id, parent_id
+            )));
+        }
+    }
+
+    let statement = connection.prepare(
+        "
+        with recursive subtree(id) as (
+          select :id
+          union
+          select todoItems.id
+          from todoItems
+          join subtree on todoItems.parentId = subtree.id
+          where todoItems.deletedAt = (select deletedAt from todoItems where id = :id)
+        )
+        update todoItems
+        set deletedAt = null
+        where id in (select id from subtree)
+      ",
+    )?;
+
+    statement.bind_by_name(":id", &*id)?;
+
+    statement.next()?;
+
+    // The parent may have been completed while the item was in the trash.
+    reopen_ancestors(connection, &id)?;
+
+    return get(connection, &id);
+}
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedTodoItem {
+    pub todo_item: TodoItem,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// The items in the trash, most recently deleted first. Subtasks that were
+/// trashed along with their parent are left out, since they're restored with
+/// it.
+pub fn get_trashed(connection: &mut Connection) -> Result<Vec<TrashedTodoItem>, AppError> {
+    let mut todo_items = Vec::new();
+
+    let statement = connection.prepare(&format!(
+        "
+        select {}, deletedAt
+        from todoItems
+        where
+          deletedAt is not null
+          and not exists (
+            select 1
+            from todoItems as parents
+            where
+              parents.id = todoItems.parentId
+              and parents.deletedAt = todoItems.deletedAt
+          )
+        order by deletedAt desc, id
+      ",
+        COLUMNS
+    ))?;
+
+    // The deletion time comes after the todo item's own columns.
+    let deleted_at_index = statement.column_count() - 1;
+
+    while let State::Row = statement.next()? {
+        todo_items.push(TrashedTodoItem {
+            todo_item: read(statement)?,
+            deleted_at: timestamp::from_sql(&statement.read::<String>(deleted_at_index)?)?,
+        });
+    }
+
+    return Ok(todo_items);
+}
+
 pub fn add_tag(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     todo_item_id: String,
     tag_id: String,
-) -> Result<(), sqlite::Error> {
+) -> Result<(), AppError> {
+    // The foreign keys don't know about the trash.
+    get(connection, &todo_item_id)?;
+    tag::get(connection, tag_id.clone())?;
+
     let todo_item_tag_id = Uuid::new_v4().to_string();
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection
-        .prepare(
-            "
+    let statement = connection.prepare(
+        "
         insert into todoItemsTags (
           id,
           todoItemId,
@@ -316,79 +1705,126 @@ pub fn add_tag(
           ?
         )
       ",
-        )
-        .unwrap();
-
-    statement.bind(1, &*todo_item_tag_id).unwrap();
-    statement.bind(2, &*todo_item_id).unwrap();
-    statement.bind(3, &*tag_id).unwrap();
+    )?;
 
-    statement.next().unwrap();
+    statement.bind(1, &*todo_item_tag_id)?;
+    statement.bind(2, &*todo_item_id)?;
+    statement.bind(3, &*tag_id)?;
 
-    println!("Adding tag to todo item");
-    println!("  - id: {}", todo_item_id);
-    println!("  - tagId: {}", tag_id);
+    statement
+        .next()
+        .map_err(|error| match AppError::from(error) {
+            AppError::Conflict(message) if message.contains("FOREIGN KEY") => {
+                AppError::NotFound(format!(
+                    "Todo item {} or tag {} does not exist",
+                    todo_item_id, tag_id
+                ))
+            }
+            AppError::Conflict(_) => AppError::Conflict(format!(
+                "Tag {} is already on todo item {}",
+                tag_id, todo_item_id
+            )),
+            error => error,
+        })?;
 
     Ok(())
 }
 
 pub fn remove_tag(
-    app_handle: &AppHandle,
+    connection: &mut Connection,
     todo_item_id: String,
     tag_id: String,
-) -> Result<(), sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-    let mut statement = connection
-        .prepare(
-            "
+) -> Result<(), AppError> {
+    let statement = connection.prepare(
+        "
         delete from todoItemsTags
         where
           todoItemId = ?
           and tagId = ?
       ",
-        )
-        .unwrap();
+    )?;
 
-    statement.bind(1, &*todo_item_id).unwrap();
-    statement.bind(2, &*tag_id).unwrap();
+    statement.bind(1, &*todo_item_id)?;
+    statement.bind(2, &*tag_id)?;
 
-    statement.next().unwrap();
+    statement.next()?;
 
-    println!("Removing tag from todo item");
-    println!("  - id: {}", todo_item_id);
-    println!("  - tagId: {}", tag_id);
+    connection.expect_changes(&format!(
+        "Tag {} is not on todo item {}",
+        tag_id, todo_item_id
+    ))?;
 
     Ok(())
 }
 
-pub fn get_tags(app_handle: &AppHandle, id: String) -> Result<Vec<tag::Tag>, sqlite::Error> {
-    let connection = database::initialize_database(app_handle)?;
-
+pub fn get_tags(connection: &mut Connection, id: String) -> Result<Vec<tag::Tag>, AppError> {
     let mut tags: Vec<tag::Tag> = Vec::new();
-    let mut tag_ids: Vec<String> = Vec::new();
 
-    let mut statement = connection
-        .prepare(
-            "
+    let statement = connection.prepare(
+        "
       select
-        tagId
+        tags.id,
+        tags.color,
+        tags.name
       from todoItemsTags
+      join tags on tags.id = todoItemsTags.tagId
       where
-        todoItemId = ?
+        todoItemsTags.todoItemId = ?
+        and tags.deletedAt is null
+      order by tags.name collate nocase
     ",
-        )
-        .unwrap();
+    )?;
 
-    statement.bind(1, &*id).unwrap();
+    statement.bind(1, &*id)?;
 
-    while let State::Row = statement.next().unwrap() {
-        tag_ids.push(statement.read::<String>(0).unwrap())
+    while let State::Row = statement.next()? {
+        tags.push(tag::Tag {
+            id: statement.read::<String>(0)?,
+            color: statement.read::<String>(1)?,
+            name: statement.read::<String>(2)?,
+        });
     }
 
-    for tag_id in tag_ids {
-        if let Ok(tag) = tag::get(app_handle, tag_id) {
-            tags.push(tag);
-        }
+    return Ok(tags);
+}
+
+/// The tags on each of the items, by item id, found in a single query. Items
+/// without tags map to an empty list.
+pub fn get_tags_for_items(
+    connection: &mut Connection,
+    ids: Vec<String>,
+) -> Result<HashMap<String, Vec<tag::Tag>>, AppError> {
+    let mut tags: HashMap<String, Vec<tag::Tag>> =
+        ids.iter().map(|id| (id.clone(), Vec::new())).collect();
+
+    let statement = connection.prepare(
+        "
+      select
+        todoItemsTags.todoItemId,
+        tags.id,
+        tags.color,
+        tags.name
+      from todoItemsTags
+      join tags on tags.id = todoItemsTags.tagId
+      where
+        todoItemsTags.todoItemId in (select value from json_each(?))
+        and tags.deletedAt is null
+      order by tags.name collate nocase
+    ",
+    )?;
+
+    // The ids are bound as one JSON array so the statement is the same
+    // however many there are.
+    statement.bind(1, &*serde_json::to_string(&ids)?)?;
+
+    while let State::Row = statement.next()? {
+        tags.entry(statement.read::<String>(0)?)
+            .or_default()
+            .push(tag::Tag {
+                id: statement.read::<String>(1)?,
+                color: statement.read::<String>(2)?,
+                name: statement.read::<String>(3)?,
+            });
     }
 
     return Ok(tags);
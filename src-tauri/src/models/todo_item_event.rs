@@ -0,0 +1,200 @@
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::timestamp;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlite::{State, Statement};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TodoItemEventKind {
+    Created,
+    FieldChanged,
+    Completed,
+    Uncompleted,
+    Deleted,
+    Restored,
+    TagAdded,
+    TagRemoved,
+}
+
+impl TodoItemEventKind {
+    pub fn from_sql(value: &str) -> Result<TodoItemEventKind, AppError> {
+        match value {
+            "created" => Ok(TodoItemEventKind::Created),
+            "fieldChanged" => Ok(TodoItemEventKind::FieldChanged),
+            "completed" => Ok(TodoItemEventKind::Completed),
+            "uncompleted" => Ok(TodoItemEventKind::Uncompleted),
+            "deleted" => Ok(TodoItemEventKind::Deleted),
+            "restored" => Ok(TodoItemEventKind::Restored),
+            "tagAdded" => Ok(TodoItemEventKind::TagAdded),
+            "tagRemoved" => Ok(TodoItemEventKind::TagRemoved),
+            _ => Err(AppError::Database(format!(
+                "Invalid todo item event kind {} in database",
+                value
+            ))),
+        }
+    }
+}
+
+/// One change in a todo item's history. Field changes name the `field` and
+/// hold its value before and after, as stored in the database. Tag changes
+/// hold the tag's id as the new value when it was added and the old value
+/// when it was removed.
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItemEvent {
+    pub id: i64,
+    pub todo_item_id: String,
+    pub occurred_at: DateTime<Utc>,
+    pub kind: TodoItemEventKind,
+    pub field: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+fn read(statement: &Statement) -> Result<TodoItemEvent, AppError> {
+    return Ok(TodoItemEvent {
+        id: statement.read::<i64>(0)?,
+        todo_item_id: statement.read::<String>(1)?,
+        occurred_at: timestamp::from_sql(&statement.read::<String>(2)?)?,
+        kind: TodoItemEventKind::from_sql(&statement.read::<String>(3)?)?,
+        field: statement.read::<Option<String>>(4)?,
+        old_value: statement.read::<Option<String>>(5)?,
+        new_value: statement.read::<Option<String>>(6)?,
+    });
+}
+
+/// The item's history, most recent first. Events are recorded by triggers, so
+/// there's no way to add to it other than changing the item.
+pub fn get_history(
+    connection: &mut Connection,
+    todo_item_id: String,
+) -> Result<Vec<TodoItemEvent>, AppError> {
+    let mut events = Vec::new();
+
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          todoItemId,
+          occurredAt,
+          kind,
+          field,
+          oldValue,
+          newValue
+        from todoItemEvents
+        where todoItemId = ?
+        order by id desc
+      ",
+    )?;
+
+    statement.bind(1, &*todo_item_id)?;
+
+    while let State::Row = statement.next()? {
+        events.push(read(statement)?)
+    }
+
+    if events.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "Todo item {} does not exist",
+            todo_item_id
+        )));
+    }
+
+    return Ok(events);
+}
+
+fn get(connection: &mut Connection, id: i64) -> Result<TodoItemEvent, AppError> {
+    let statement = connection.prepare(
+        "
+        select
+          id,
+          todoItemId,
+          occurredAt,
+          kind,
+          field,
+          oldValue,
+          newValue
+        from todoItemEvents
+        where id = ?
+      ",
+    )?;
+
+    statement.bind(1, id)?;
+
+    if let State::Row = statement.next()? {
+        return read(statement);
+    }
+
+    Err(AppError::NotFound(format!(
+        "Todo item event {} does not exist",
+        id
+    )))
+}
+
+/// Puts the field changed by the event back to what it was before the event.
+/// Restoring is itself a change, so it shows up in the history too. Only
+/// text fields can be restored, since the others have rules of their own
+/// about what they can be set to.
+pub fn restore_field(
+    connection: &mut Connection,
+    todo_item_id: String,
+    event_id: i64,
+) -> Result<(), AppError> {
+    let event = get(connection, event_id)?;
+
+    if event.todo_item_id != todo_item_id {
+        return Err(AppError::NotFound(format!(
+            "Todo item event {} is not part of todo item {}",
+            event_id, todo_item_id
+        )));
+    }
+
+    let sql = match (event.kind, event.field.as_deref()) {
+        (TodoItemEventKind::FieldChanged, Some("title")) => {
+            "
+            update todoItems
+            set title = ?
+            where
+              id = ?
+              and deletedAt is null
+          "
+        }
+        (TodoItemEventKind::FieldChanged, Some("description")) => {
+            "
+            update todoItems
+            set description = ?
+            where
+              id = ?
+              and deletedAt is null
+          "
+        }
+        (TodoItemEventKind::FieldChanged, Some("notes")) => {
+            "
+            update todoItems
+            set notes = ?
+            where
+              id = ?
+              and deletedAt is null
+          "
+        }
+        _ => {
+            return Err(AppError::Validation(format!(
+                "Todo item event {} isn't a change to the title, description or notes",
+                event_id
+            )))
+        }
+    };
+
+    let statement = connection.prepare(sql)?;
+
+    statement.bind(1, event.old_value.as_deref())?;
+    statement.bind(2, &*todo_item_id)?;
+
+    statement.next()?;
+
+    connection.expect_changes(&format!("Todo item {} does not exist", todo_item_id))?;
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::database::Connection;
+use crate::error::AppError;
+use crate::journal;
+use crate::models::setting;
+use crate::models::tag;
+use crate::models::todo_item;
+use crate::timestamp;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Trash {
+    pub todo_items: Vec<todo_item::TrashedTodoItem>,
+    pub tags: Vec<tag::TrashedTag>,
+}
+
+pub fn get(connection: &mut Connection) -> Result<Trash, AppError> {
+    return Ok(Trash {
+        todo_items: todo_item::get_trashed(connection)?,
+        tags: tag::get_trashed(connection)?,
+    });
+}
+
+// Trashed items only have trashed subtasks, so the foreign key never deletes
+// an item that isn't in the trash.
+fn delete_trashed_before(connection: &mut Connection, before: &str) -> Result<i64, AppError> {
+    let mut deleted = 0;
+
+    for sql in [
+        "
+        delete from todoItems
+        where deletedAt < ?
+      ",
+        "
+        delete from tags
+        where deletedAt < ?
+      ",
+    ] {
+        let statement = connection.prepare(sql)?;
+
+        statement.bind(1, before)?;
+
+        statement.next()?;
+
+        deleted += connection.changes()?;
+    }
+
+    return Ok(deleted);
+}
+
+/// Deletes everything in the trash for good.
+pub fn empty(connection: &mut Connection) -> Result<(), AppError> {
+    connection.savepoint(|connection| {
+        for sql in [
+            "
+            delete from todoItems
+            where deletedAt is not null
+          ",
+            "
+            delete from tags
+            where deletedAt is not null
+          ",
+        ] {
+            connection.prepare(sql)?.next()?;
+        }
+
+        // Undoing what put them in the trash, or anything else that changed
+        // them, can't bring them back.
+        journal::clear(connection)
+    })
+}
+
+pub fn get_retention_days(connection: &mut Connection) -> Result<u32, AppError> {
+    let value = setting::get(connection, setting::TRASH_RETENTION_DAYS)?;
+
+    return value
+        .parse::<u32>()
+        .map_err(|_| AppError::Database(format!("Invalid trash retention {} in database", value)));
+}
+
+pub fn set_retention_days(connection: &mut Connection, days: u32) -> Result<(), AppError> {
+    if days == 0 {
+        return Err(AppError::Validation(String::from(
+            "Deleted items have to stay in the trash for at least a day",
+        )));
+    }
+
+    return setting::set(connection, setting::TRASH_RETENTION_DAYS, &days.to_string());
+}
+
+/// Deletes whatever has been in the trash for longer than the retention
+/// setting. Runs when the app starts.
+pub fn purge_expired(connection: &mut Connection, now: DateTime<Utc>) -> Result<(), AppError> {
+    connection.savepoint(|connection| {
+        let days = get_retention_days(connection)?;
+        let cutoff = timestamp::to_sql(&(now - Duration::days(i64::from(days))));
+
+        if delete_trashed_before(connection, &cutoff)? > 0 {
+            journal::clear(connection)?;
+        }
+
+        Ok(())
+    })
+}
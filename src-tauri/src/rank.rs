@@ -0,0 +1,404 @@
+use crate::error::AppError;
+
+// A rank is an integer part followed by an optional fraction, written in
+// these digits, which are in ASCII order so that ranks sort correctly as text.
+//
+// The first character of the integer part says how many digits follow it:
+// `a` to `z` are one to 26 digits of a positive integer and `Z` to `A` are one
+// to 26 digits of a negative one, so appending to a list only makes ranks
+// longer every few thousand items. A fraction never ends in the lowest digit,
+// so there is always room for another rank before any rank.
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const ZERO: u8 = DIGITS[0];
+const LAST: u8 = DIGITS[DIGITS.len() - 1];
+
+fn digit_value(digit: u8) -> usize {
+    DIGITS.iter().position(|d| *d == digit).unwrap_or(0)
+}
+
+fn invalid(rank: &str) -> AppError {
+    AppError::Database(format!("Invalid rank {} in database", rank))
+}
+
+fn integer_length(head: u8) -> Option<usize> {
+    match head {
+        b'a'..=b'z' => Some(usize::from(head - b'a') + 2),
+        b'A'..=b'Z' => Some(usize::from(b'Z' - head) + 2),
+        _ => None,
+    }
+}
+
+// The smallest integer has no integer before it, so it's kept free for ranks
+// that need to go before it.
+fn is_smallest(integer: &str) -> bool {
+    integer.starts_with('A') && integer.bytes().skip(1).all(|digit| digit == ZERO)
+}
+
+/// Splits a rank into its integer part and fraction.
+fn split(rank: &str) -> Result<(&str, &str), AppError> {
+    let length = rank
+        .bytes()
+        .next()
+        .and_then(integer_length)
+        .filter(|length| *length <= rank.len())
+        .ok_or_else(|| invalid(rank))?;
+
+    let (integer, fraction) = rank.split_at(length);
+
+    if (is_smallest(integer) && fraction.is_empty())
+        || fraction.bytes().last() == Some(ZERO)
+        || !rank.bytes().skip(1).all(|digit| DIGITS.contains(&digit))
+    {
+        return Err(invalid(rank));
+    }
+
+    return Ok((integer, fraction));
+}
+
+fn increment(integer: &str) -> Option<String> {
+    let head = integer.as_bytes()[0];
+    let mut digits = integer.as_bytes()[1..].to_vec();
+
+    for digit in digits.iter_mut().rev() {
+        if *digit != LAST {
+            *digit = DIGITS[digit_value(*digit) + 1];
+            return Some(format!(
+                "{}{}",
+                head as char,
+                String::from_utf8_lossy(&digits)
+            ));
+        }
+
+        *digit = ZERO;
+    }
+
+    // Every digit carried, so the integer needs a different length.
+    match head {
+        b'Z' => Some(format!("a{}", ZERO as char)),
+        b'z' => None,
+        _ => {
+            let head = head + 1;
+
+            if head > b'a' {
+                digits.push(ZERO);
+            } else {
+                digits.pop();
+            }
+
+            Some(format!(
+                "{}{}",
+                head as char,
+                String::from_utf8_lossy(&digits)
+            ))
+        }
+    }
+}
+
+fn decrement(integer: &str) -> Option<String> {
+    let head = integer.as_bytes()[0];
+    let mut digits = integer.as_bytes()[1..].to_vec();
+
+    for digit in digits.iter_mut().rev() {
+        if *digit != ZERO {
+            *digit = DIGITS[digit_value(*digit) - 1];
+            return Some(format!(
+                "{}{}",
+                head as char,
+                String::from_utf8_lossy(&digits)
+            ));
+        }
+
+        *digit = LAST;
+    }
+
+    match head {
+        b'a' => Some(format!("Z{}", LAST as char)),
+        b'A' => None,
+        _ => {
+            let head = head - 1;
+
+            if head < b'Z' {
+                digits.push(LAST);
+            } else {
+                digits.pop();
+            }
+
+            Some(format!(
+                "{}{}",
+                head as char,
+                String::from_utf8_lossy(&digits)
+            ))
+        }
+    }
+}
+
+/// The midpoint of the fractions `low` and `high`, where an empty `low` is
+/// zero and a missing `high` is one.
+fn midpoint(low: &str, high: Option<&str>) -> String {
+    if let Some(high) = high {
+        // Skip the digits both fractions share, reading past the end of `low`
+        // as zeros.
+        let shared = high
+            .bytes()
+            .enumerate()
+            .take_while(|(index, digit)| low.as_bytes().get(*index).unwrap_or(&ZERO) == digit)
+            .count();
+
+        if shared > 0 {
+            return format!(
+                "{}{}",
+                &high[..shared],
+                midpoint(low.get(shared..).unwrap_or(""), Some(&high[shared..]))
+            );
+        }
+    }
+
+    let low_digit = low.bytes().next().map_or(0, digit_value);
+    let high_digit = high
+        .and_then(|high| high.bytes().next())
+        .map_or(DIGITS.len(), digit_value);
+
+    if high_digit - low_digit > 1 {
+        return String::from(DIGITS[(low_digit + high_digit + 1) / 2] as char);
+    }
+
+    // The first digits are consecutive, so the midpoint needs another digit.
+    match high {
+        Some(high) if high.len() > 1 => String::from(&high[..1]),
+        _ => format!(
+            "{}{}",
+            DIGITS[low_digit] as char,
+            midpoint(low.get(1..).unwrap_or(""), None)
+        ),
+    }
+}
+
+/// Checks a rank that didn't come from this module, such as one in an import.
+pub fn validate(rank: &str) -> Result<(), AppError> {
+    split(rank)
+        .map(|_| ())
+        .map_err(|_| AppError::Validation(format!("{} is not a valid rank", rank)))
+}
+
+/// A rank that sorts after `low` and before `high`. Either side can be left
+/// open to get a rank at the start or end of the list.
+pub fn between(low: Option<&str>, high: Option<&str>) -> Result<String, AppError> {
+    if let (Some(low), Some(high)) = (low, high) {
+        if low >= high {
+            return Err(AppError::Validation(format!(
+                "Rank {} does not come before rank {}",
+                low, high
+            )));
+        }
+    }
+
+    match (low, high) {
+        (None, None) => Ok(format!("a{}", ZERO as char)),
+        (None, Some(high)) => {
+            let (integer, fraction) = split(high)?;
+
+            if is_smallest(integer) {
+                return Ok(format!("{}{}", integer, midpoint("", Some(fraction))));
+            }
+
+            if !fraction.is_empty() {
+                return Ok(String::from(integer));
+            }
+
+            match decrement(integer) {
+                Some(lower) if is_smallest(&lower) => {
+                    Ok(format!("{}{}", lower, midpoint("", None)))
+                }
+                Some(lower) => Ok(lower),
+                None => Err(invalid(high)),
+            }
+        }
+        (Some(low), None) => {
+            let (integer, fraction) = split(low)?;
+
+            match increment(integer) {
+                Some(integer) => Ok(integer),
+                None => Ok(format!("{}{}", integer, midpoint(fraction, None))),
+            }
+        }
+        (Some(low), Some(high)) => {
+            let (low_integer, low_fraction) = split(low)?;
+            let (high_integer, high_fraction) = split(high)?;
+
+            if low_integer == high_integer {
+                return Ok(format!(
+                    "{}{}",
+                    low_integer,
+                    midpoint(low_fraction, Some(high_fraction))
+                ));
+            }
+
+            match increment(low_integer) {
+                Some(integer) if integer.as_str() < high => Ok(integer),
+                _ => Ok(format!("{}{}", low_integer, midpoint(low_fraction, None))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repeat(digit: u8, count: usize) -> String {
+        return String::from(digit as char).repeat(count);
+    }
+
+    #[test]
+    fn increments_integers() {
+        assert_eq!(increment("a0").as_deref(), Some("a1"));
+        assert_eq!(increment("b0z").as_deref(), Some("b10"));
+        assert_eq!(increment("Z0").as_deref(), Some("Z1"));
+    }
+
+    #[test]
+    fn increments_integers_that_carry() {
+        assert_eq!(increment("az").as_deref(), Some("b00"));
+        assert_eq!(increment("bzz").as_deref(), Some("c000"));
+        assert_eq!(increment("Zz").as_deref(), Some("a0"));
+        assert_eq!(increment("Yzz").as_deref(), Some("Z0"));
+        assert_eq!(increment(&format!("z{}", repeat(LAST, 26))), None);
+    }
+
+    #[test]
+    fn decrements_integers() {
+        assert_eq!(decrement("a1").as_deref(), Some("a0"));
+        assert_eq!(decrement("b10").as_deref(), Some("b0z"));
+        assert_eq!(decrement("Z1").as_deref(), Some("Z0"));
+    }
+
+    #[test]
+    fn decrements_integers_that_carry() {
+        assert_eq!(decrement("b00").as_deref(), Some("az"));
+        assert_eq!(decrement("a0").as_deref(), Some("Zz"));
+        assert_eq!(decrement("Z0").as_deref(), Some("Yzz"));
+        assert_eq!(decrement(&format!("A{}", repeat(ZERO, 26))), None);
+    }
+
+    #[test]
+    fn takes_midpoints_of_fractions() {
+        assert_eq!(midpoint("", None), "V");
+        assert_eq!(midpoint("V", None), "l");
+        assert_eq!(midpoint("", Some("V")), "G");
+        assert_eq!(midpoint("1", Some("12")), "11");
+        assert_eq!(midpoint("", Some("01")), "00V");
+    }
+
+    #[test]
+    fn takes_midpoints_of_adjacent_digits() {
+        assert_eq!(midpoint("", Some("1")), "0V");
+        assert_eq!(midpoint("1", Some("2")), "1V");
+        assert_eq!(midpoint("1z", Some("2")), "1zV");
+        assert_eq!(midpoint("z", None), "zV");
+        assert_eq!(midpoint("1", Some("2V")), "2");
+    }
+
+    #[test]
+    fn midpoints_sort_between_their_fractions() {
+        let fractions = ["", "01", "0V", "1", "1z", "2", "V", "Vz", "z", "zz"];
+
+        for (index, low) in fractions.iter().enumerate() {
+            for high in fractions[index + 1..]
+                .iter()
+                .map(|high| Some(*high))
+                .chain([None])
+            {
+                let middle = midpoint(low, high);
+
+                assert!(*low < middle.as_str(), "{} {:?}", low, high);
+                assert!(
+                    high.map_or(true, |high| middle.as_str() < high),
+                    "{} {:?}",
+                    low,
+                    high
+                );
+                assert_ne!(middle.bytes().last(), Some(ZERO));
+            }
+        }
+    }
+
+    #[test]
+    fn ranks_open_ends() {
+        assert_eq!(between(None, None).unwrap(), "a0");
+        assert_eq!(between(Some("a0"), None).unwrap(), "a1");
+        assert_eq!(between(None, Some("a0")).unwrap(), "Zz");
+        assert_eq!(between(None, Some("a0V")).unwrap(), "a0");
+
+        let largest = format!("z{}", repeat(LAST, 26));
+        assert_eq!(
+            between(Some(&largest), None).unwrap(),
+            format!("{}V", largest)
+        );
+
+        let smallest = format!("A{}", repeat(ZERO, 26));
+        let above_smallest = format!("A{}1", repeat(ZERO, 25));
+        assert_eq!(
+            between(None, Some(&above_smallest)).unwrap(),
+            format!("{}V", smallest)
+        );
+        assert_eq!(
+            between(None, Some(&format!("{}V", smallest))).unwrap(),
+            format!("{}G", smallest)
+        );
+    }
+
+    #[test]
+    fn ranks_between_adjacent_ranks() {
+        assert_eq!(between(Some("a0"), Some("a2")).unwrap(), "a1");
+        assert_eq!(between(Some("a0"), Some("a1")).unwrap(), "a0V");
+        assert_eq!(between(Some("az"), Some("b00")).unwrap(), "azV");
+        assert_eq!(between(Some("a0"), Some("a0V")).unwrap(), "a0G");
+        assert_eq!(between(Some("a01"), Some("a02")).unwrap(), "a01V");
+    }
+
+    #[test]
+    fn keeps_ranking_before_the_same_rank() {
+        let low = "a0";
+        let mut high = String::from("a1");
+
+        for _ in 0..1000 {
+            let middle = between(Some(low), Some(&high)).unwrap();
+
+            assert!(low < middle.as_str() && middle < high);
+            validate(&middle).unwrap();
+
+            high = middle;
+        }
+    }
+
+    #[test]
+    fn rejects_ranks_out_of_order() {
+        assert!(matches!(
+            between(Some("a1"), Some("a1")),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            between(Some("a2"), Some("a1")),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn validates_ranks() {
+        for rank in ["a0", "a0V", "Zz", "b00", "b001"] {
+            assert!(validate(rank).is_ok(), "{}", rank);
+        }
+
+        for rank in [
+            "",
+            "a",
+            "b0",
+            "a00",
+            "a0!",
+            "0",
+            &format!("A{}", repeat(ZERO, 26)),
+        ] {
+            assert!(validate(rank).is_err(), "{}", rank);
+        }
+    }
+}
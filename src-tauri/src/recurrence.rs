@@ -0,0 +1,595 @@
+use crate::error::AppError;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Which date the next occurrence of a recurring item is counted from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RecurrenceMode {
+    FromDueDate,
+    FromCompletion,
+}
+
+impl RecurrenceMode {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            RecurrenceMode::FromDueDate => "due",
+            RecurrenceMode::FromCompletion => "completion",
+        }
+    }
+
+    pub fn from_sql(value: &str) -> Result<RecurrenceMode, AppError> {
+        match value {
+            "due" => Ok(RecurrenceMode::FromDueDate),
+            "completion" => Ok(RecurrenceMode::FromCompletion),
+            _ => Err(AppError::Database(format!(
+                "Invalid recurrence mode {} in database",
+                value
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A `BYDAY` entry such as `MO`, `1MO` (the first Monday) or `-1FR` (the last
+/// Friday) of the month or year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// The subset of an RFC 5545 RRULE that todo items support: `FREQ`,
+/// `INTERVAL`, `BYDAY`, `COUNT` and `UNTIL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+}
+
+// Stops the search for the next occurrence of rules that can never match,
+// such as the fifth Monday of every twelfth month starting in a short one.
+const MAX_PERIODS: i64 = 1000;
+
+fn invalid(value: &str, reason: &str) -> AppError {
+    AppError::Validation(format!("Invalid recurrence rule {}: {}", value, reason))
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn format_weekday(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_by_day(rule: &str, value: &str) -> Result<ByDay, AppError> {
+    let not_a_weekday = || invalid(rule, &format!("{} is not a weekday", value));
+
+    // Weekdays are two ASCII letters, so a split inside another character
+    // can't be in front of one.
+    let split = value.len().saturating_sub(2);
+    let (ordinal, weekday) = match (value.get(..split), value.get(split..)) {
+        (Some(ordinal), Some(weekday)) => (ordinal, weekday),
+        _ => return Err(not_a_weekday()),
+    };
+    let weekday = parse_weekday(weekday).ok_or_else(not_a_weekday)?;
+
+    if ordinal.is_empty() {
+        return Ok(ByDay {
+            ordinal: None,
+            weekday,
+        });
+    }
+
+    match ordinal.trim_start_matches('+').parse::<i32>() {
+        Ok(ordinal) if ordinal != 0 && ordinal.abs() <= 53 => Ok(ByDay {
+            ordinal: Some(ordinal),
+            weekday,
+        }),
+        _ => Err(not_a_weekday()),
+    }
+}
+
+fn parse_until(rule: &str, value: &str) -> Result<NaiveDate, AppError> {
+    // UNTIL is either a date or a date-time; only the date part matters for
+    // items that recur on whole days.
+    let date = value.get(0..8).unwrap_or(value);
+
+    return NaiveDate::parse_from_str(date, "%Y%m%d")
+        .map_err(|_| invalid(rule, &format!("{} is not a date", value)));
+}
+
+fn add_months(date: NaiveDate, months: i64) -> Option<(i32, u32)> {
+    let index = i64::from(date.year()) * 12 + i64::from(date.month0()) + months;
+
+    return Some((
+        i32::try_from(index.div_euclid(12)).ok()?,
+        index.rem_euclid(12) as u32 + 1,
+    ));
+}
+
+fn days_in_period(first: NaiveDate, last: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    first.iter_days().take_while(move |date| *date <= last)
+}
+
+/// Expands `by_day` within the period from `first` to `last`, the way BYDAY
+/// applies to a month or a year.
+fn expand_by_day(by_day: &[ByDay], first: NaiveDate, last: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+
+    for entry in by_day {
+        let matching: Vec<NaiveDate> = days_in_period(first, last)
+            .filter(|date| date.weekday() == entry.weekday)
+            .collect();
+
+        match entry.ordinal {
+            None => dates.extend(matching),
+            Some(ordinal) if ordinal > 0 => {
+                dates.extend(matching.get(ordinal as usize - 1).copied());
+            }
+            Some(ordinal) => {
+                let index = matching.len() as i64 + i64::from(ordinal);
+
+                if index >= 0 {
+                    dates.push(matching[index as usize]);
+                }
+            }
+        }
+    }
+
+    dates.sort();
+    dates.dedup();
+
+    return dates;
+}
+
+impl Rule {
+    pub fn parse(value: &str) -> Result<Rule, AppError> {
+        let rule = value.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, part_value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(value, &format!("{} is not a KEY=VALUE pair", part)))?;
+
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match part_value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => {
+                            return Err(invalid(
+                                value,
+                                &format!("FREQ={} is not supported", part_value),
+                            ))
+                        }
+                    })
+                }
+                "INTERVAL" => {
+                    interval = part_value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| invalid(value, "INTERVAL must be a positive number"))?
+                }
+                "BYDAY" => {
+                    for day in part_value.split(',') {
+                        by_day.push(parse_by_day(value, &day.to_ascii_uppercase())?);
+                    }
+                }
+                "COUNT" => {
+                    count = Some(
+                        part_value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(|| invalid(value, "COUNT must be a positive number"))?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_until(value, part_value)?),
+                "WKST" if part_value.eq_ignore_ascii_case("MO") => {}
+                _ => {
+                    return Err(invalid(
+                        value,
+                        &format!("{} is not supported", key.to_ascii_uppercase()),
+                    ))
+                }
+            }
+        }
+
+        let frequency = frequency.ok_or_else(|| invalid(value, "FREQ is required"))?;
+
+        if count.is_some() && until.is_some() {
+            return Err(invalid(value, "COUNT and UNTIL can't both be set"));
+        }
+
+        if by_day.iter().any(|entry| entry.ordinal.is_some())
+            && !matches!(frequency, Frequency::Monthly | Frequency::Yearly)
+        {
+            return Err(invalid(
+                value,
+                "numbered BYDAY entries need FREQ=MONTHLY or FREQ=YEARLY",
+            ));
+        }
+
+        Ok(Rule {
+            frequency,
+            interval,
+            by_day,
+            count,
+            until,
+        })
+    }
+
+    /// The candidate dates of the period `index` intervals after the one
+    /// containing `start`.
+    fn period(&self, start: NaiveDate, index: i64) -> Vec<NaiveDate> {
+        let interval = i64::from(self.interval);
+
+        match self.frequency {
+            Frequency::Daily => {
+                let date = start + Duration::days(index * interval);
+
+                if self.by_day.is_empty()
+                    || self
+                        .by_day
+                        .iter()
+                        .any(|entry| entry.weekday == date.weekday())
+                {
+                    vec![date]
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Weekly => {
+                let offset = i64::from(start.weekday().num_days_from_monday());
+                let monday = start - Duration::days(offset) + Duration::weeks(index * interval);
+
+                if self.by_day.is_empty() {
+                    return vec![monday + Duration::days(offset)];
+                }
+
+                let mut dates: Vec<NaiveDate> = self
+                    .by_day
+                    .iter()
+                    .map(|entry| {
+                        monday + Duration::days(i64::from(entry.weekday.num_days_from_monday()))
+                    })
+                    .collect();
+
+                dates.sort();
+                dates.dedup();
+
+                dates
+            }
+            Frequency::Monthly => {
+                let (year, month) = match add_months(start, index * interval) {
+                    Some(year_and_month) => year_and_month,
+                    None => return vec![],
+                };
+
+                if self.by_day.is_empty() {
+                    // Months without the start's day of the month are skipped.
+                    return NaiveDate::from_ymd_opt(year, month, start.day())
+                        .into_iter()
+                        .collect();
+                }
+
+                let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+                let (next_year, next_month) = add_months(first, 1).unwrap();
+                let last =
+                    NaiveDate::from_ymd_opt(next_year, next_month, 1).unwrap() - Duration::days(1);
+
+                expand_by_day(&self.by_day, first, last)
+            }
+            Frequency::Yearly => {
+                let year = match i32::try_from(i64::from(start.year()) + index * interval) {
+                    Ok(year) => year,
+                    Err(_) => return vec![],
+                };
+
+                if self.by_day.is_empty() {
+                    return NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                        .into_iter()
+                        .collect();
+                }
+
+                match (
+                    NaiveDate::from_ymd_opt(year, 1, 1),
+                    NaiveDate::from_ymd_opt(year, 12, 31),
+                ) {
+                    (Some(first), Some(last)) => expand_by_day(&self.by_day, first, last),
+                    _ => vec![],
+                }
+            }
+        }
+    }
+
+    /// The first occurrence after `after` of the series that starts at
+    /// `start`, ignoring `COUNT`, which depends on how many occurrences came
+    /// before.
+    pub fn next_after(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        for index in 0..MAX_PERIODS {
+            for date in self.period(start, index) {
+                if self.until.map_or(false, |until| date > until) {
+                    return None;
+                }
+
+                if date > after && date >= start {
+                    return Some(date);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+
+        write!(f, "FREQ={}", frequency)?;
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+
+        if !self.by_day.is_empty() {
+            let by_day: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|entry| match entry.ordinal {
+                    Some(ordinal) => format!("{}{}", ordinal, format_weekday(entry.weekday)),
+                    None => String::from(format_weekday(entry.weekday)),
+                })
+                .collect();
+
+            write!(f, ";BYDAY={}", by_day.join(","))?;
+        }
+
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn next(rule: &str, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        Rule::parse(rule).unwrap().next_after(start, after)
+    }
+
+    #[test]
+    fn parses_and_formats_rules() {
+        let rule = Rule::parse("RRULE:FREQ=monthly;INTERVAL=2;BYDAY=+1MO,-1fr;COUNT=5").unwrap();
+
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(
+            rule.by_day,
+            vec![
+                ByDay {
+                    ordinal: Some(1),
+                    weekday: Weekday::Mon,
+                },
+                ByDay {
+                    ordinal: Some(-1),
+                    weekday: Weekday::Fri,
+                },
+            ]
+        );
+        assert_eq!(rule.count, Some(5));
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=MONTHLY;INTERVAL=2;BYDAY=1MO,-1FR;COUNT=5"
+        );
+    }
+
+    #[test]
+    fn parses_until_dates_and_date_times() {
+        assert_eq!(
+            Rule::parse("FREQ=DAILY;UNTIL=20240301").unwrap().until,
+            Some(date(2024, 3, 1))
+        );
+        assert_eq!(
+            Rule::parse("FREQ=DAILY;UNTIL=20240301T120000Z")
+                .unwrap()
+                .until,
+            Some(date(2024, 3, 1))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for rule in [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=DAILY;COUNT=2;UNTIL=20240301",
+            "FREQ=DAILY;UNTIL=2024",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=MONTHLY;BYDAY=0MO",
+            "FREQ=MONTHLY;BYDAY=54MO",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=WEEKLY;BYSETPOS=1",
+            "FREQ=WEEKLY;BYDAY",
+        ] {
+            assert!(
+                matches!(Rule::parse(rule), Err(AppError::Validation(_))),
+                "{}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_by_day_values_split_inside_a_character() {
+        for rule in [
+            "FREQ=WEEKLY;BYDAY=éA",
+            "FREQ=WEEKLY;BYDAY=é",
+            "FREQ=MONTHLY;BYDAY=1éMO",
+            "FREQ=MONTHLY;BYDAY=MOé",
+        ] {
+            assert!(
+                matches!(Rule::parse(rule), Err(AppError::Validation(_))),
+                "{}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn expands_weekly_by_day() {
+        let rule = "FREQ=WEEKLY;BYDAY=MO,FR";
+
+        assert_eq!(
+            next(rule, date(2024, 1, 3), date(2024, 1, 3)),
+            Some(date(2024, 1, 5))
+        );
+        assert_eq!(
+            next(rule, date(2024, 1, 3), date(2024, 1, 5)),
+            Some(date(2024, 1, 8))
+        );
+        assert_eq!(
+            next("FREQ=WEEKLY;INTERVAL=2", date(2024, 1, 3), date(2024, 1, 3)),
+            Some(date(2024, 1, 17))
+        );
+        assert_eq!(
+            next(
+                "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR",
+                date(2024, 1, 5),
+                date(2024, 1, 5)
+            ),
+            Some(date(2024, 1, 8))
+        );
+    }
+
+    #[test]
+    fn expands_negative_by_day_ordinals() {
+        let last_friday = "FREQ=MONTHLY;BYDAY=-1FR";
+
+        assert_eq!(
+            next(last_friday, date(2024, 1, 26), date(2024, 1, 26)),
+            Some(date(2024, 2, 23))
+        );
+        assert_eq!(
+            next(last_friday, date(2024, 1, 26), date(2024, 2, 23)),
+            Some(date(2024, 3, 29))
+        );
+        assert_eq!(
+            next(
+                "FREQ=MONTHLY;BYDAY=-2MO",
+                date(2024, 3, 1),
+                date(2024, 3, 1)
+            ),
+            Some(date(2024, 3, 18))
+        );
+        assert_eq!(
+            next("FREQ=YEARLY;BYDAY=-1SU", date(2024, 1, 1), date(2024, 1, 1)),
+            Some(date(2024, 12, 29))
+        );
+    }
+
+    #[test]
+    fn skips_months_and_years_without_the_start_day() {
+        let monthly = "FREQ=MONTHLY";
+
+        assert_eq!(
+            next(monthly, date(2024, 1, 31), date(2024, 1, 31)),
+            Some(date(2024, 3, 31))
+        );
+        assert_eq!(
+            next(monthly, date(2024, 1, 31), date(2024, 3, 31)),
+            Some(date(2024, 5, 31))
+        );
+        assert_eq!(
+            next("FREQ=YEARLY", date(2024, 2, 29), date(2024, 2, 29)),
+            Some(date(2028, 2, 29))
+        );
+        assert_eq!(
+            next(
+                "FREQ=MONTHLY;BYDAY=5MO",
+                date(2024, 1, 29),
+                date(2024, 1, 29)
+            ),
+            Some(date(2024, 4, 29))
+        );
+    }
+
+    #[test]
+    fn stops_at_until() {
+        let rule = "FREQ=DAILY;UNTIL=20240103";
+
+        assert_eq!(
+            next(rule, date(2024, 1, 1), date(2024, 1, 2)),
+            Some(date(2024, 1, 3))
+        );
+        assert_eq!(next(rule, date(2024, 1, 1), date(2024, 1, 3)), None);
+    }
+
+    #[test]
+    fn ignores_count() {
+        assert_eq!(
+            next("FREQ=DAILY;COUNT=2", date(2024, 1, 1), date(2024, 1, 5)),
+            Some(date(2024, 1, 6))
+        );
+    }
+}
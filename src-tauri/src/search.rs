@@ -0,0 +1,106 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+// Marks the start and end of each match in highlights and snippets. They're
+// control characters so they can't clash with anything in a todo item.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+/// A piece of highlighted text, which either matched the search or didn't.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TextPart {
+    pub text: String,
+    pub is_match: bool,
+}
+
+/// Splits text from SQLite's `highlight()` or `snippet()` at the match markers.
+pub fn split_matches(text: &str) -> Vec<TextPart> {
+    let mut parts = Vec::new();
+    let mut is_match = false;
+
+    for piece in text.split(|c| c == MATCH_START || c == MATCH_END) {
+        if !piece.is_empty() {
+            parts.push(TextPart {
+                text: String::from(piece),
+                is_match,
+            });
+        }
+
+        is_match = !is_match;
+    }
+
+    return parts;
+}
+
+fn quote(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// Translates a search as typed by the user into an FTS5 query. Words must
+/// all match, `"quoted words"` match as a phrase, a trailing `*` matches any
+/// word starting with what comes before it, and a leading `-` excludes items
+/// that match the word or phrase after it. Everything else is matched
+/// literally, so no search can be an invalid FTS5 query.
+pub fn to_fts_query(search: &str) -> Result<Option<String>, AppError> {
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+    let mut chars = search.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let is_excluded = chars.next_if_eq(&'-').is_some();
+
+        let term = if chars.next_if_eq(&'"').is_some() {
+            // An unterminated phrase runs to the end of the search.
+            let phrase: String = chars.by_ref().take_while(|c| *c != '"').collect();
+
+            quote(phrase.trim())
+        } else {
+            let mut word = String::new();
+
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '"') {
+                word.push(c);
+            }
+
+            match word.strip_suffix('*') {
+                Some(prefix) if !prefix.trim_end_matches('*').is_empty() => {
+                    format!("{} *", quote(prefix.trim_end_matches('*')))
+                }
+                _ => quote(word.trim_end_matches('*')),
+            }
+        };
+
+        if term != "\"\"" {
+            if is_excluded {
+                excluded.push(term);
+            } else {
+                included.push(term);
+            }
+        }
+
+        if chars.peek().is_none() {
+            break;
+        }
+    }
+
+    if included.is_empty() {
+        if excluded.is_empty() {
+            return Ok(None);
+        }
+
+        return Err(AppError::Validation(String::from(
+            "A search needs at least one word that isn't excluded",
+        )));
+    }
+
+    let mut query = format!("({})", included.join(" AND "));
+
+    for term in excluded {
+        query.push_str(" NOT ");
+        query.push_str(&term);
+    }
+
+    return Ok(Some(query));
+}
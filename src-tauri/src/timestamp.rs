@@ -0,0 +1,81 @@
+use crate::error::AppError;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+
+pub fn parse_instant(value: &str) -> Result<DateTime<Utc>, AppError> {
+    return DateTime::parse_from_rfc3339(value)
+        .map(|instant| instant.with_timezone(&Utc))
+        .map_err(|_| AppError::Validation(format!("{} is not an RFC 3339 timestamp", value)));
+}
+
+pub fn parse_timezone(value: &str) -> Result<Tz, AppError> {
+    return value
+        .parse::<Tz>()
+        .map_err(|_| AppError::Validation(format!("{} is not an IANA time zone", value)));
+}
+
+pub fn parse_date(value: &str) -> Result<NaiveDate, AppError> {
+    return NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::Validation(format!("{} is not a YYYY-MM-DD date", value)));
+}
+
+pub fn parse_time(value: &str) -> Result<NaiveTime, AppError> {
+    return NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| AppError::Validation(format!("{} is not an HH:MM time", value)));
+}
+
+pub fn date_to_sql(date: &NaiveDate) -> String {
+    return date.format("%Y-%m-%d").to_string();
+}
+
+pub fn date_from_sql(value: &str) -> Result<NaiveDate, AppError> {
+    return NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::Database(format!("Invalid date {} in database", value)));
+}
+
+pub fn time_to_sql(time: &NaiveTime) -> String {
+    return time.format("%H:%M").to_string();
+}
+
+pub fn time_from_sql(value: &str) -> Result<NaiveTime, AppError> {
+    return NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| AppError::Database(format!("Invalid time {} in database", value)));
+}
+
+/// Formats an instant the way it's stored in the database. Every stored
+/// instant has the same width and a `Z` suffix, so they sort correctly as text.
+pub fn to_sql(instant: &DateTime<Utc>) -> String {
+    return instant.to_rfc3339_opts(SecondsFormat::Millis, true);
+}
+
+pub fn from_sql(value: &str) -> Result<DateTime<Utc>, AppError> {
+    return DateTime::parse_from_rfc3339(value)
+        .map(|instant| instant.with_timezone(&Utc))
+        .map_err(|_| AppError::Database(format!("Invalid timestamp {} in database", value)));
+}
+
+/// The first instant of `date` in `timezone`. Midnight doesn't exist on some
+/// DST transition days, in which case the day starts at the first local time
+/// that does.
+pub fn start_of_day(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    let mut local = date.and_hms_opt(0, 0, 0).unwrap();
+
+    loop {
+        if let Some(instant) = timezone.from_local_datetime(&local).earliest() {
+            return instant.with_timezone(&Utc);
+        }
+
+        local += Duration::minutes(15);
+    }
+}
+
+/// The instants bounding `date` in `timezone`, as a half-open range.
+pub fn day_bounds(date: NaiveDate, timezone: Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let next_date = date.succ_opt().unwrap_or(date);
+
+    return (
+        start_of_day(date, timezone),
+        start_of_day(next_date, timezone),
+    );
+}
@@ -1,5 +1,4 @@
 import { createSignal, Index, Setter, For } from 'solid-js'
-import { invoke } from '@tauri-apps/api'
 import classnames from 'classnames'
 import { useTheme } from '../../contexts/Theme'
 import cloneDeep from 'lodash.clonedeep'
@@ -9,6 +8,11 @@ import styles from './TagsTable.module.css'
 import IconButton from '../IconButton'
 import Icon from '../Icon'
 import { Tag } from '../../types/Models'
+import {
+  createTag,
+  deleteTag as deleteTagCommand,
+  updateTag,
+} from '../../generated/bindings'
 
 interface Errors {
   name: string[]
@@ -62,11 +66,11 @@ export default function TagsTable(props: Props) {
       return
     }
 
-    await invoke('update_tag', {
+    await updateTag(
       id,
-      ...(name === 'name' && { name: value }),
-      ...(name === 'color' && { color: value }),
-    })
+      name === 'name' ? value : null,
+      name === 'color' ? value : null
+    )
 
     tags.splice(tagIndex, 1, {
       ...tag,
@@ -77,18 +81,13 @@ export default function TagsTable(props: Props) {
   }
 
   const addTagRow = async () => {
-    const newTag = JSON.parse(
-      await invoke('create_tag', {
-        name: 'New Tag',
-        color: generateRandomColor(),
-      })
-    ) as Tag
+    const newTag = await createTag('New Tag', generateRandomColor())
 
     props.mutateTags((prev) => [...(prev || []), newTag])
   }
 
   const deleteTag = async (id: string) => {
-    await invoke('delete_tag', { id })
+    await deleteTagCommand(id)
 
     props.mutateTags((prev) => prev?.filter((tag) => tag.id !== id))
   }
@@ -20,9 +20,14 @@ export interface Props {
   title: string
   isCompleted: boolean
   tags: Tag[]
+  childCount: number
+  completedChildCount: number
   onDelete: (id: string) => void
   onComplete: (id: string, isCompleted: boolean) => void
   onClick: (id: string) => JSX.EventHandler<HTMLDivElement, MouseEvent>
+  draggable?: boolean
+  onDragStart?: (id: string) => void
+  onDrop?: (id: string) => void
   style?: string | JSX.CSSProperties | undefined
 }
 
@@ -45,6 +50,17 @@ export default function TodoCard(props: Props) {
         [styles['todo-card--can-hover']]: getCanHover(),
       })}
       onClick={props.onClick(props.id)}
+      draggable={props.draggable}
+      onDragStart={() => props.onDragStart?.(props.id)}
+      onDragOver={(e) => {
+        if (props.draggable) {
+          e.preventDefault()
+        }
+      }}
+      onDrop={(e) => {
+        e.preventDefault()
+        props.onDrop?.(props.id)
+      }}
       onAnimationEnd={() => {
         if (getIsEntering()) {
           setIsEntering(false)
@@ -78,6 +94,11 @@ export default function TodoCard(props: Props) {
           >
             {props.title}
           </span>
+          {props.childCount > 0 && (
+            <span class={styles['todo-card__progress']}>
+              {props.completedChildCount}/{props.childCount}
+            </span>
+          )}
           {props.tags.map((tag) => (
             <span
               class={styles['todo-card__tag']}
@@ -11,7 +11,10 @@ import { useTheme } from '../../contexts/Theme'
 import styles from './TodoEditPanel.module.css'
 import { UpdateTodoItemArgs } from '../../types/Operations'
 import { useKeyboardHandler } from '../../contexts/App'
-import { invoke } from '@tauri-apps/api'
+import {
+  addTagToTodoItem,
+  removeTagFromTodoItem,
+} from '../../generated/bindings'
 
 interface Tag {
   id: string
@@ -195,10 +198,7 @@ export default function TodoEditPanel(props: Props) {
             )
 
             for (const tag of tagsToAdd) {
-              await invoke('add_tag_to_todo_item', {
-                todoItemId,
-                tagId: tag.id,
-              })
+              await addTagToTodoItem(todoItemId, tag.id)
 
               props.mutateTodoItems((todoItems) => {
                 if (!todoItems) return todoItems
@@ -217,10 +217,7 @@ export default function TodoEditPanel(props: Props) {
             }
 
             for (const tag of tagsToRemove) {
-              await invoke('remove_tag_from_todo_item', {
-                todoItemId,
-                tagId: tag.id,
-              })
+              await removeTagFromTodoItem(todoItemId, tag.id)
 
               props.mutateTodoItems((todoItems) => {
                 if (!todoItems) return todoItems
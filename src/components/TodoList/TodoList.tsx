@@ -3,26 +3,39 @@ import {
   createResource,
   createSignal,
   Index,
+  onCleanup,
   Suspense,
   useContext,
 } from 'solid-js'
-import { format } from 'date-fns'
 import { debounce } from 'debounce'
 
 import TodoCard from '../TodoCard'
 import TodoEditPanel from '../TodoEditPanel'
-import { Tag } from '../../generated/graphql'
 import AddTodoItemWidget from '../AddTodoItemWidget'
 import DateHeader from '../DateHeader'
 import SkeletonTodoCard from '../SkeletonTodoCard'
+import Select, { Option } from '../Select'
 import { ValueOf } from '../../utils/ValueOf'
 import { useTheme } from '../../contexts/Theme'
 
 import styles from './TodoList.module.css'
-import { invoke } from '@tauri-apps/api'
+import {
+  completeTodoItem,
+  createTodoItem,
+  deleteTodoItem as deleteTodoItemCommand,
+  getTodoItems,
+  moveTodoItem as moveTodoItemCommand,
+  redo,
+  SortOrder,
+  uncompleteTodoItem,
+  undo,
+  updateTodoItem as updateTodoItemCommand,
+} from '../../generated/bindings'
 import { TodoItem, TodoItemModel } from '../../types/Models'
 import { UpdateTodoItemArgs } from '../../types/Operations'
 import { TagsContext } from '../../contexts/Tags'
+import { useMessage } from '../../contexts/Message'
+import { AppError, getErrorMessage } from '../../types/Errors'
 
 function padDateComponent(component: number) {
   return component < 10 ? `0${component}` : component
@@ -34,174 +47,185 @@ function getDateStringWithoutTime(date: Date) {
   )}-${padDateComponent(date.getDate())}`
 }
 
-function getTimeStringWithoutDate(date: Date) {
-  return `${padDateComponent(date.getHours())}:${padDateComponent(
-    date.getMinutes()
-  )}`
+function getTimezone() {
+  return Intl.DateTimeFormat().resolvedOptions().timeZone
 }
 
-function getTimezoneStringWithoutDate(date: Date) {
-  return format(date, 'XXX')
+function toTodoItem(todoItem: TodoItemModel): TodoItem {
+  return {
+    id: todoItem.id,
+    title: todoItem.title,
+    description: todoItem.description,
+    dateCreated: new Date(todoItem.createdAt),
+    dateCompleted: todoItem.completedAt ? new Date(todoItem.completedAt) : null,
+    notes: todoItem.notes,
+    tags: todoItem.tags,
+    isCompleted: todoItem.isCompleted,
+    parentId: todoItem.parentId,
+    childCount: todoItem.childCount,
+    completedChildCount: todoItem.completedChildCount,
+  }
 }
 
-function getDateFromComponents({
-  date,
-  time,
-  timezone,
-}: {
-  date: string
-  time: string
-  timezone: string
-}) {
-  return new Date(`${date}T${time}${timezone}`)
-}
-
-async function fetchTodoItemTags(id: string) {
-  return JSON.parse(
-    await invoke('get_todo_item_tags', { todoItemId: id })
-  ) as Tag[]
-}
+const sortOptions: Option<SortOrder>[] = [
+  { value: 'manual', label: 'Manual' },
+  { value: 'priority', label: 'Priority' },
+  { value: 'dueDate', label: 'Due date' },
+  { value: 'created', label: 'Created' },
+  { value: 'title', label: 'Title' },
+  { value: 'completedAt', label: 'Completed' },
+]
 
 async function fetchTodoItems({
   currentDate,
+  sort,
 }: {
   currentDate: Date
+  sort: SortOrder
 }): Promise<TodoItem[]> {
-  const todoItems = JSON.parse(
-    await invoke('get_todo_items', {
-      dateCompleted: getDateStringWithoutTime(currentDate),
-    })
-  ) as TodoItemModel[]
-
-  return await Promise.all(
-    todoItems.map(async (todoItem) => {
-      return {
-        id: todoItem.id,
-        title: todoItem.title,
-        description: todoItem.description,
-        dateCreated: getDateFromComponents({
-          date: todoItem.date_created,
-          time: todoItem.time_created,
-          timezone: todoItem.timezone_created,
-        }),
-        dateCompleted:
-          todoItem.date_completed &&
-          todoItem.time_completed &&
-          todoItem.timezone_completed
-            ? getDateFromComponents({
-                date: todoItem.date_completed,
-                time: todoItem.time_completed,
-                timezone: todoItem.timezone_completed,
-              })
-            : null,
-        notes: todoItem.notes,
-        tags: await fetchTodoItemTags(todoItem.id),
-        isCompleted: todoItem.is_completed,
-      }
-    })
+  const todoItems = await getTodoItems(
+    getDateStringWithoutTime(currentDate),
+    getTimezone(),
+    sort
   )
-}
 
-async function fetchTags() {
-  return await invoke('get_tags')
+  return todoItems.map(toTodoItem)
 }
 
 export default function TodoList() {
   const [tagsState] = useContext(TagsContext)
   const [theme] = useTheme()
+  const [, { setMessage }] = useMessage()
   const [getPanelIsClosing, setPanelIsClosing] = createSignal(false)
   const [getCurrentDate, setCurrentDate] = createSignal<Date>(new Date())
-  const [todoItems, { mutate }] = createResource(
-    () => ({ currentDate: getCurrentDate() }),
+  const [getSort, setSort] = createSignal<SortOrder>('manual')
+  const [todoItems, { mutate, refetch }] = createResource(
+    () => ({ currentDate: getCurrentDate(), sort: getSort() }),
     fetchTodoItems
   )
   const [getSelectedItemId, setSelectedItemId] = createSignal<string>()
+  const [getDraggedItemId, setDraggedItemId] = createSignal<string>()
 
   const getSelectedItem = () =>
     todoItems()?.find((item) => item.id === getSelectedItemId())
 
+  // Items come back from the backend already sorted.
   const getIncompleteItems = () =>
-    todoItems()
-      ?.filter((item) => !item.isCompleted)
-      .sort((a, b) => {
-        return a.dateCreated.getTime() - b.dateCreated.getTime()
-      })
+    todoItems()?.filter((item) => !item.isCompleted)
   const getCompletedItems = () =>
-    todoItems()
-      ?.filter((item) => {
-        const dateCompleted = item.dateCompleted
-          ? new Date(item.dateCompleted)
-          : undefined
-
-        return (
-          item.isCompleted &&
-          dateCompleted &&
-          getDateStringWithoutTime(dateCompleted) ===
-            getDateStringWithoutTime(getCurrentDate())
-        )
-      })
-      .sort((a, b) => {
-        // Forcing non-null assertion because the filter should ensure the dateCompleted is not null
-        return a.dateCompleted!.getTime() - b.dateCompleted!.getTime()
-      })
+    todoItems()?.filter((item) => {
+      const dateCompleted = item.dateCompleted
+        ? new Date(item.dateCompleted)
+        : undefined
+
+      return (
+        item.isCompleted &&
+        dateCompleted &&
+        getDateStringWithoutTime(dateCompleted) ===
+          getDateStringWithoutTime(getCurrentDate())
+      )
+    })
+
+  const showError = (error: AppError) => {
+    setMessage({ message: getErrorMessage(error), type: 'error' })
+  }
 
   const addTodoItem = async (title: string) => {
-    const dateCreated = new Date()
-
-    const createdTodoItem = JSON.parse(
-      await invoke('create_todo_item', {
-        title,
-        dateCreated: getDateStringWithoutTime(dateCreated),
-        timeCreated: getTimeStringWithoutDate(dateCreated),
-        timezoneCreated: getTimezoneStringWithoutDate(dateCreated),
+    const createdTodoItem = await createTodoItem(
+      title,
+      getTimezone(),
+      null,
+      null
+    )
+
+    mutate((prev) => [...(prev ?? []), toTodoItem(createdTodoItem)])
+  }
+
+  // Completing or deleting an item with subtasks, or a subtask, also changes
+  // the items above and below it, so those are reloaded instead of updated in
+  // place.
+  const isInHierarchy = (id: string) => {
+    const item = todoItems()?.find((item) => item.id === id)
+
+    return Boolean(item && (item.parentId || item.childCount > 0))
+  }
+
+  // Ctrl+Z undoes the last change to the items and Ctrl+Shift+Z redoes it.
+  // Text fields keep their own undo.
+  function handleUndoKeyDown(event: KeyboardEvent) {
+    const target = event.target as HTMLElement
+
+    if (
+      !(event.ctrlKey || event.metaKey) ||
+      event.key.toLowerCase() !== 'z' ||
+      target.closest('input, textarea, [contenteditable]')
+    ) {
+      return
+    }
+
+    event.preventDefault()
+
+    const isRedo = event.shiftKey
+
+    ;(isRedo ? redo() : undo())
+      .then((step) => {
+        if (!step) {
+          return
+        }
+
+        refetch()
+        setMessage({
+          message: `${isRedo ? 'Redone' : 'Undone'}: ${step.description}`,
+          type: 'success',
+        })
       })
-    ) as TodoItemModel
+      .catch(showError)
+  }
+  document.addEventListener('keydown', handleUndoKeyDown)
+  onCleanup(() => {
+    document.removeEventListener('keydown', handleUndoKeyDown)
+  })
 
-    mutate((prev) => [
-      ...(prev ?? []),
-      {
-        ...createdTodoItem,
-        isCompleted: createdTodoItem.is_completed,
-        dateCreated: getDateFromComponents({
-          date: createdTodoItem.date_created,
-          time: createdTodoItem.time_created,
-          timezone: createdTodoItem.timezone_created,
-        }),
-        dateCompleted:
-          createdTodoItem.date_completed &&
-          createdTodoItem.time_completed &&
-          createdTodoItem.timezone_completed
-            ? getDateFromComponents({
-                date: createdTodoItem.date_completed,
-                time: createdTodoItem.time_completed,
-                timezone: createdTodoItem.timezone_completed,
-              })
-            : null,
-        tags: [],
-      },
-    ])
+  const showDeleted = () => {
+    setMessage({ message: 'Moved to the trash', type: 'success' })
   }
 
   const deleteTodoItem = (id: string) => {
+    if (isInHierarchy(id)) {
+      deleteTodoItemCommand(id, getTimezone())
+        .then(refetch)
+        .then(showDeleted)
+        .catch(showError)
+      return
+    }
+
     mutate((prev) => prev?.filter((item) => item.id !== id) ?? [])
 
-    void invoke('delete_todo_item', { id })
+    deleteTodoItemCommand(id, getTimezone()).then(showDeleted).catch(showError)
   }
 
   const toggleTodoItem = async (id: string, isCompleted: boolean) => {
     const currentDate = getCurrentDate()
+    let nextOccurrence: TodoItemModel | null = null
 
-    if (isCompleted) {
-      await invoke('uncomplete_todo_item', {
-        id,
-      })
-    } else {
-      await invoke('complete_todo_item', {
-        id,
-        dateCompleted: getDateStringWithoutTime(currentDate),
-        timeCompleted: getTimeStringWithoutDate(currentDate),
-        timezoneCompleted: getTimezoneStringWithoutDate(currentDate),
-      })
+    try {
+      if (isCompleted) {
+        await uncompleteTodoItem(id)
+      } else {
+        nextOccurrence = await completeTodoItem(
+          id,
+          currentDate.toISOString(),
+          getTimezone()
+        )
+      }
+    } catch (error) {
+      showError(error as AppError)
+      return
+    }
+
+    if (isInHierarchy(id)) {
+      refetch()
+      return
     }
 
     mutate((prev) =>
@@ -211,6 +235,59 @@ export default function TodoList() {
         dateCompleted: item.id === id && isCompleted ? null : currentDate,
       }))
     )
+
+    if (
+      nextOccurrence &&
+      (!nextOccurrence.startDate ||
+        nextOccurrence.startDate <= getDateStringWithoutTime(currentDate))
+    ) {
+      const next = nextOccurrence
+
+      mutate((prev) => [...(prev ?? []), toTodoItem(next)])
+    }
+  }
+
+  // Dropping an item onto another puts it where that item was, moving the
+  // rest of the list up or down to make room.
+  const moveTodoItem = (id: string, targetId: string) => {
+    const items = getIncompleteItems() ?? []
+    const from = items.findIndex((item) => item.id === id)
+    const to = items.findIndex((item) => item.id === targetId)
+
+    if (from === -1 || to === -1 || from === to) {
+      return
+    }
+
+    // Without the dragged item, the target's old index is where the dragged
+    // item goes either way: after the target when moving down, before it when
+    // moving up.
+    const remaining = items.filter((item) => item.id !== id)
+    const before = remaining[to - 1]
+    const after = remaining[to]
+
+    mutate((prev) => {
+      const dragged = prev?.find((item) => item.id === id)
+      const list = (prev ?? []).filter((item) => item.id !== id)
+
+      if (!dragged) {
+        return list
+      }
+
+      const insertAt = after
+        ? list.findIndex((item) => item.id === after.id)
+        : list.findIndex((item) => item.id === before.id) + 1
+
+      list.splice(insertAt, 0, dragged)
+
+      return list
+    })
+
+    moveTodoItemCommand(id, before?.id ?? null, after?.id ?? null).catch(
+      (error) => {
+        showError(error)
+        refetch()
+      }
+    )
   }
 
   const updateTodoItem = debounce(
@@ -230,10 +307,12 @@ export default function TodoList() {
         }))
       )
 
-      void invoke('update_todo_item', {
+      updateTodoItemCommand(
         id,
-        [fieldName]: value,
-      })
+        fieldName === 'title' ? value ?? null : null,
+        fieldName === 'description' ? value ?? null : null,
+        fieldName === 'notes' ? value ?? null : null
+      ).catch(showError)
     },
     500
   )
@@ -257,6 +336,14 @@ export default function TodoList() {
         <div class={styles['lists']}>
           <div class={styles['incomplete-list']}>
             <h2 class={styles['list-heading']}>Todo</h2>
+            <Select
+              label="Sort by"
+              options={sortOptions}
+              value={getSort()}
+              onChange={(option: Option<SortOrder>) =>
+                setSort(option.value)
+              }
+            />
             <Index each={getIncompleteItems()}>
               {(item, index) => (
                 <TodoCard
@@ -268,6 +355,8 @@ export default function TodoList() {
                   title={item().title}
                   isCompleted={item().isCompleted}
                   tags={item().tags}
+                  childCount={item().childCount}
+                  completedChildCount={item().completedChildCount}
                   onDelete={deleteTodoItem}
                   onComplete={toggleTodoItem}
                   onClick={(id) => () => {
@@ -277,6 +366,17 @@ export default function TodoList() {
                       setSelectedItemId(id)
                     }
                   }}
+                  draggable={getSort() === 'manual'}
+                  onDragStart={setDraggedItemId}
+                  onDrop={(id) => {
+                    const draggedItemId = getDraggedItemId()
+
+                    if (draggedItemId) {
+                      moveTodoItem(draggedItemId, id)
+                    }
+
+                    setDraggedItemId(undefined)
+                  }}
                 />
               )}
             </Index>
@@ -295,6 +395,8 @@ export default function TodoList() {
                     title={item().title}
                     isCompleted={item().isCompleted}
                     tags={item().tags}
+                    childCount={item().childCount}
+                    completedChildCount={item().completedChildCount}
                     onDelete={deleteTodoItem}
                     onComplete={toggleTodoItem}
                     onClick={(id) => () => {
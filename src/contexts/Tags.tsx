@@ -7,7 +7,7 @@ import {
   createResource,
 } from 'solid-js'
 import { Tag } from '../types/Models'
-import { invoke } from '@tauri-apps/api'
+import { getTags } from '../generated/bindings'
 
 interface State {
   tags: Resource<Tag[] | undefined>
@@ -31,7 +31,7 @@ export const TagsContext = createContext<Context>([
 ])
 
 async function fetchTags() {
-  return JSON.parse(await invoke('get_tags')) as Tag[]
+  return await getTags()
 }
 
 export default function TagsProvider(props: { children: JSXElement }) {
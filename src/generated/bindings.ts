@@ -0,0 +1,329 @@
+/* eslint-disable */
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+declare global {
+    interface Window {
+        __TAURI_INVOKE__<T>(cmd: string, args?: Record<string, unknown>): Promise<T>;
+    }
+}
+
+// Function avoids 'window not defined' in SSR
+const invoke = () => window.__TAURI_INVOKE__;
+
+export function getTodoItems(date: string, timezone: string, sort: SortOrder) {
+    return invoke()<TodoItem[]>("get_todo_items", { date,timezone,sort })
+}
+
+export function getProjectTodoItems(projectId: string, date: string, timezone: string, sort: SortOrder) {
+    return invoke()<TodoItem[]>("get_project_todo_items", { projectId,date,timezone,sort })
+}
+
+export function searchTodoItems(query: string, filters: SearchFilters) {
+    return invoke()<SearchResult[]>("search_todo_items", { query,filters })
+}
+
+export function createTodoItem(title: string, timezone: string, parentId: string | null, projectId: string | null) {
+    return invoke()<TodoItem>("create_todo_item", { title,timezone,parentId,projectId })
+}
+
+export function completeTodoItem(id: string, completedAt: string, timezone: string) {
+    return invoke()<TodoItem | null>("complete_todo_item", { id,completedAt,timezone })
+}
+
+export function completeTodoItems(ids: string[], completedAt: string, timezone: string) {
+    return invoke()<TodoItem[]>("complete_todo_items", { ids,completedAt,timezone })
+}
+
+export function uncompleteTodoItem(id: string) {
+    return invoke()<null>("uncomplete_todo_item", { id })
+}
+
+export function updateTodoItem(id: string, title: string | null, description: string | null, notes: string | null) {
+    return invoke()<null>("update_todo_item", { id,title,description,notes })
+}
+
+export function setTodoItemDueDate(id: string, dueDate: string | null, dueTime: string | null) {
+    return invoke()<null>("set_todo_item_due_date", { id,dueDate,dueTime })
+}
+
+export function setTodoItemStartDate(id: string, startDate: string | null) {
+    return invoke()<null>("set_todo_item_start_date", { id,startDate })
+}
+
+export function setTodoItemPriority(id: string, priority: Priority) {
+    return invoke()<null>("set_todo_item_priority", { id,priority })
+}
+
+export function setTodoItemRecurrence(id: string, rule: string, mode: RecurrenceMode) {
+    return invoke()<null>("set_todo_item_recurrence", { id,rule,mode })
+}
+
+export function skipTodoItemOccurrence(id: string, date: string, timezone: string) {
+    return invoke()<TodoItem>("skip_todo_item_occurrence", { id,date,timezone })
+}
+
+export function endTodoItemRecurrence(id: string) {
+    return invoke()<null>("end_todo_item_recurrence", { id })
+}
+
+export function moveTodoItem(id: string, beforeId: string | null, afterId: string | null) {
+    return invoke()<null>("move_todo_item", { id,beforeId,afterId })
+}
+
+export function getTodoItemChildren(id: string) {
+    return invoke()<TodoItem[]>("get_todo_item_children", { id })
+}
+
+export function setTodoItemParent(id: string, parentId: string | null) {
+    return invoke()<null>("set_todo_item_parent", { id,parentId })
+}
+
+export function setTodoItemProject(id: string, projectId: string | null) {
+    return invoke()<null>("set_todo_item_project", { id,projectId })
+}
+
+export function setTodoItemCompletesWithChildren(id: string, completesWithChildren: boolean, timezone: string) {
+    return invoke()<null>("set_todo_item_completes_with_children", { id,completesWithChildren,timezone })
+}
+
+export function getTodoItemsDueOn(date: string) {
+    return invoke()<TodoItem[]>("get_todo_items_due_on", { date })
+}
+
+export function getOverdueTodoItems(date: string) {
+    return invoke()<TodoItem[]>("get_overdue_todo_items", { date })
+}
+
+export function getUpcomingTodoItems(date: string, days: number) {
+    return invoke()<TodoItem[]>("get_upcoming_todo_items", { date,days })
+}
+
+export function getUndatedTodoItems(date: string) {
+    return invoke()<TodoItem[]>("get_undated_todo_items", { date })
+}
+
+export function deleteTodoItem(id: string, timezone: string) {
+    return invoke()<null>("delete_todo_item", { id,timezone })
+}
+
+export function deleteTodoItems(ids: string[], timezone: string) {
+    return invoke()<null>("delete_todo_items", { ids,timezone })
+}
+
+export function restoreTodoItem(id: string) {
+    return invoke()<TodoItem>("restore_todo_item", { id })
+}
+
+export function getTodoItemHistory(id: string) {
+    return invoke()<TodoItemEvent[]>("get_todo_item_history", { id })
+}
+
+export function restoreTodoItemField(id: string, eventId: number) {
+    return invoke()<TodoItem>("restore_todo_item_field", { id,eventId })
+}
+
+export function addTagToTodoItem(todoItemId: string, tagId: string) {
+    return invoke()<null>("add_tag_to_todo_item", { todoItemId,tagId })
+}
+
+export function removeTagFromTodoItem(todoItemId: string, tagId: string) {
+    return invoke()<null>("remove_tag_from_todo_item", { todoItemId,tagId })
+}
+
+export function getTodoItemTags(todoItemId: string) {
+    return invoke()<Tag[]>("get_todo_item_tags", { todoItemId })
+}
+
+export function getTagsForItems(ids: string[]) {
+    return invoke()<{ [key in string]: Tag[] }>("get_tags_for_items", { ids })
+}
+
+export function getTags() {
+    return invoke()<Tag[]>("get_tags")
+}
+
+export function createTag(name: string, color: string) {
+    return invoke()<Tag>("create_tag", { name,color })
+}
+
+export function updateTag(id: string, name: string | null, color: string | null) {
+    return invoke()<Tag>("update_tag", { id,name,color })
+}
+
+export function deleteTag(id: string) {
+    return invoke()<null>("delete_tag", { id })
+}
+
+export function restoreTag(id: string) {
+    return invoke()<Tag>("restore_tag", { id })
+}
+
+export function getProjects() {
+    return invoke()<Project[]>("get_projects")
+}
+
+export function createProject(name: string, color: string, icon: string | null) {
+    return invoke()<Project>("create_project", { name,color,icon })
+}
+
+export function updateProject(id: string, name: string | null, color: string | null, icon: string | null, sortOrder: number | null) {
+    return invoke()<Project>("update_project", { id,name,color,icon,sortOrder })
+}
+
+export function setProjectArchived(id: string, isArchived: boolean) {
+    return invoke()<null>("set_project_archived", { id,isArchived })
+}
+
+export function deleteProject(id: string) {
+    return invoke()<null>("delete_project", { id })
+}
+
+export function getSmartLists() {
+    return invoke()<SmartList[]>("get_smart_lists")
+}
+
+export function createSmartList(name: string, filter: string) {
+    return invoke()<SmartList>("create_smart_list", { name,filter })
+}
+
+export function updateSmartList(id: string, name: string | null, filter: string | null, sortOrder: number | null) {
+    return invoke()<SmartList>("update_smart_list", { id,name,filter,sortOrder })
+}
+
+export function deleteSmartList(id: string) {
+    return invoke()<null>("delete_smart_list", { id })
+}
+
+export function evaluateSmartList(id: string, date: string, sort: SortOrder) {
+    return invoke()<TodoItem[]>("evaluate_smart_list", { id,date,sort })
+}
+
+export function evaluateFilter(filter: string, date: string, sort: SortOrder) {
+    return invoke()<TodoItem[]>("evaluate_filter", { filter,date,sort })
+}
+
+export function listTrash() {
+    return invoke()<Trash>("list_trash")
+}
+
+export function emptyTrash() {
+    return invoke()<null>("empty_trash")
+}
+
+export function getTrashRetentionDays() {
+    return invoke()<number>("get_trash_retention_days")
+}
+
+export function setTrashRetentionDays(days: number) {
+    return invoke()<null>("set_trash_retention_days", { days })
+}
+
+export function undo() {
+    return invoke()<JournalStep | null>("undo")
+}
+
+export function redo() {
+    return invoke()<JournalStep | null>("redo")
+}
+
+export function getJournalState() {
+    return invoke()<JournalState>("get_journal_state")
+}
+
+export function exportData(path: string) {
+    return invoke()<null>("export_data", { path })
+}
+
+export function importData(path: string, mode: ImportMode) {
+    return invoke()<ImportSummary>("import_data", { path,mode })
+}
+
+export function exportTodoTxt(path: string) {
+    return invoke()<null>("export_todo_txt", { path })
+}
+
+export function importTodoTxt(path: string, timezone: string) {
+    return invoke()<ImportSummary>("import_todo_txt", { path,timezone })
+}
+
+export function exportIcal(path: string) {
+    return invoke()<null>("export_ical", { path })
+}
+
+export function importIcal(path: string, timezone: string) {
+    return invoke()<ImportSummary>("import_ical", { path,timezone })
+}
+
+export function getIcalFeed() {
+    return invoke()<FeedStatus>("get_ical_feed")
+}
+
+export function setIcalFeedEnabled(enabled: boolean) {
+    return invoke()<null>("set_ical_feed_enabled", { enabled })
+}
+
+export function previewCsv(path: string, limit: number) {
+    return invoke()<CsvPreview>("preview_csv", { path,limit })
+}
+
+export function importCsv(path: string, mapping: CsvMapping, timezone: string) {
+    return invoke()<CsvImportReport>("import_csv", { path,mapping,timezone })
+}
+
+export function exportCsv(path: string, fields: Field[]) {
+    return invoke()<null>("export_csv", { path,fields })
+}
+
+export function renderMarkdown(options: MarkdownOptions) {
+    return invoke()<string>("render_markdown", { options })
+}
+
+export function exportMarkdown(path: string, options: MarkdownOptions) {
+    return invoke()<null>("export_markdown", { path,options })
+}
+
+export function getDefaultMarkdownTemplate() {
+    return invoke()<string>("get_default_markdown_template")
+}
+
+export function importMarkdown(path: string, timezone: string) {
+    return invoke()<ImportSummary>("import_markdown", { path,timezone })
+}
+
+export function exportTaskwarrior(path: string, timezone: string) {
+    return invoke()<null>("export_taskwarrior", { path,timezone })
+}
+
+export function importTaskwarrior(path: string, timezone: string) {
+    return invoke()<ImportSummary>("import_taskwarrior", { path,timezone })
+}
+
+export type ColumnMapping = { column: number; field: Field }
+export type CsvColumn = { index: number; name: string; field: Field | null }
+export type CsvImportReport = { summary: ImportSummary; errors: CsvRowError[] }
+export type CsvMapping = { columns: ColumnMapping[]; hasHeader: boolean; dateFormat: string | null }
+export type CsvPreview = { columns: CsvColumn[]; hasHeader: boolean; dateFormat: string | null; rows: string[][] }
+export type CsvRowError = { line: number; message: string }
+export type FeedStatus = { enabled: boolean; path: string }
+export type Field = "title" | "description" | "notes" | "isCompleted" | "createdAt" | "completedAt" | "dueDate" | "dueTime" | "startDate" | "priority" | "project" | "tags" | "recurrence"
+export type Grouping = "day" | "project" | "tag"
+export type ImportMode = "replace" | "mergeById" | "duplicateAsNew"
+export type ImportSummary = { todoItems: number; tags: number; projects: number; smartLists: number }
+export type JournalState = { undo: JournalStep | null; redo: JournalStep | null }
+export type JournalStep = { id: number; description: string; createdAt: string }
+export type MarkdownOptions = { startDate: string; endDate: string; grouping: Grouping; timezone: string; template: string | null }
+export type Priority = "none" | "low" | "medium" | "high" | "urgent"
+export type Project = { id: string; name: string; color: string; icon: string | null; isArchived: boolean; sortOrder: number }
+export type RecurrenceMode = "fromDueDate" | "fromCompletion"
+export type SearchFilters = { isCompleted: boolean | null; projectId: string | null; tagId: string | null; limit: number | null }
+export type SearchResult = { todoItem: TodoItem; title: TextPart[]; snippet: TextPart[]; rank: number }
+export type SmartList = { id: string; name: string; filter: string; sortOrder: number }
+export type SortOrder = "manual" | "priority" | "dueDate" | "created" | "title" | "completedAt"
+export type Tag = { id: string; color: string; name: string }
+export type TextPart = { text: string; isMatch: boolean }
+export type TodoItem = { id: string; title: string; description: string | null; notes: string | null; isCompleted: boolean; createdAt: string; createdTimezone: string; completedAt: string | null; completedTimezone: string | null; dueDate: string | null; dueTime: string | null; startDate: string | null; recurrence: string | null; recurrenceMode: RecurrenceMode | null; seriesId: string | null; occurrence: number | null; parentId: string | null; completesWithChildren: boolean; childCount: number; completedChildCount: number; projectId: string | null; position: string; priority: Priority; tags: Tag[] }
+export type TodoItemEvent = { id: number; todoItemId: string; occurredAt: string; kind: TodoItemEventKind; field: string | null; oldValue: string | null; newValue: string | null }
+export type TodoItemEventKind = "created" | "fieldChanged" | "completed" | "uncompleted" | "deleted" | "restored" | "tagAdded" | "tagRemoved"
+export type Trash = { todoItems: TrashedTodoItem[]; tags: TrashedTag[] }
+export type TrashedTag = { tag: Tag; deletedAt: string }
+export type TrashedTodoItem = { todoItem: TodoItem; deletedAt: string }
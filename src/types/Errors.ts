@@ -0,0 +1,16 @@
+export interface FilterError {
+  message: string
+  // UTF-16 offsets into the filter, so they can index it directly
+  start: number
+  end: number
+}
+
+export type AppError =
+  | {
+      kind: 'notFound' | 'validation' | 'database' | 'conflict' | 'io'
+      message: string
+    }
+  | { kind: 'invalidFilter'; message: FilterError }
+
+export const getErrorMessage = (error: AppError) =>
+  error.kind === 'invalidFilter' ? error.message.message : error.message
@@ -1,3 +1,7 @@
+import { Tag } from '../generated/bindings'
+
+export type { TodoItem as TodoItemModel, Tag } from '../generated/bindings'
+
 export interface TodoItem {
   id: string
   title: string
@@ -7,24 +11,7 @@ export interface TodoItem {
   dateCompleted: Date | null
   dateCreated: Date
   tags: Tag[]
-}
-
-export interface TodoItemModel {
-  id: string
-  title: string
-  description: string | null
-  notes: string | null
-  is_completed: boolean
-  date_completed: string | null
-  time_completed: string | null
-  timezone_completed: string | null
-  date_created: string
-  time_created: string
-  timezone_created: string
-}
-
-export interface Tag {
-  id: string
-  name: string
-  color: string
+  parentId: string | null
+  childCount: number
+  completedChildCount: number
 }